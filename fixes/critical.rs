@@ -45,6 +45,52 @@ pub fn create_game_session_handler(
     // ... rest of function
 }
 
+// BETTER: one validation module for every String field, used by every handler
+// that accepts one. All limits are BYTE lengths (`len()`), never char counts -
+// a 10-character emoji id is 40 bytes and must fail here, not at realloc.
+pub mod validation {
+    use super::*;
+
+    pub const MAX_SESSION_ID_BYTES: usize = 10;
+    pub const MAX_URI_BYTES: usize = 200;
+    pub const MAX_LABEL_BYTES: usize = 32;
+
+    /// session_id feeds PDA seeds: printable ASCII only, so a visually identical
+    /// id can't map to a different PDA (no zero-width, homoglyph or control chars)
+    pub fn validate_session_id(id: &str) -> Result<()> {
+        require!(!id.is_empty(), WagerError::SessionIdEmpty);
+        require!(id.len() <= MAX_SESSION_ID_BYTES, WagerError::SessionIdTooLong);
+        require!(
+            id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-'),
+            WagerError::SessionIdInvalidChars
+        );
+        Ok(())
+    }
+
+    /// Display-only fields: any UTF-8 (already guaranteed by String), capped in
+    /// bytes, no control characters
+    fn validate_display(value: &str, max_bytes: usize) -> Result<()> {
+        require!(value.len() <= max_bytes, WagerError::FieldTooLong);
+        require!(!value.chars().any(char::is_control), WagerError::FieldInvalidChars);
+        Ok(())
+    }
+
+    pub fn validate_uri(uri: &str) -> Result<()> {
+        validate_display(uri, MAX_URI_BYTES)
+    }
+
+    pub fn validate_label(label: &str) -> Result<()> {
+        validate_display(label, MAX_LABEL_BYTES)
+    }
+}
+
+// Client-side ids go through validation::validate_session_id before padding;
+// on chain create_game_session runs validate_session_id_bytes (FL-002).
+// set_metadata / create_template call validate_uri / validate_label.
+// Cases to cover: "abc" ok, 10 ASCII bytes ok, 11 rejected, "é" x5 (10 bytes,
+// 5 chars) rejected as non-ASCII, "ab\u{200B}c" rejected, "\n" rejected in both,
+// a 50-emoji (200 byte) uri accepted and 51 rejected.
+
 //FC-004:Dangerous AccountInfo Usage
 
 // ADD A VAULTSTATE ACCOUNT TO TRACK VAULT INFO THEN THE VAULT ITSELF TO BE A PDA
@@ -65,11 +111,57 @@ pub struct VaultState {
     init,
     payer = game_server,
     space = 8 + VaultState::INIT_SPACE,
-    seeds = [b"vault", session_id.as_bytes()],
+    seeds = [b"vault", game_session.key().as_ref()],
     bump
 )]
 pub vault_state: Account<'info, VaultState>,  // Type-safe with state tracking
 
+// Keeping it in step with the token account
+impl VaultState {
+    pub fn record_deposit(&mut self, amount: u64, new_player: bool) -> Result<()> {
+        require!(self.is_active, WagerError::VaultInactive);
+        self.current_balance = self.current_balance.checked_add(amount).ok_or(WagerError::ArithmeticOverflow)?;
+        if new_player {
+            self.players_deposited = self.players_deposited.checked_add(1).ok_or(WagerError::ArithmeticOverflow)?;
+        }
+        Ok(())
+    }
+
+    pub fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        self.current_balance = self.current_balance.checked_sub(amount).ok_or(WagerError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Tokens sent straight to the vault ATA surface here instead of being
+    /// split among winners. Returns the surplus over what was recorded; the
+    /// caller sends it to the treasury before paying anyone. Missing tokens
+    /// are still an error.
+    pub fn reconcile(&self, vault_token_account: &TokenAccount) -> Result<u64> {
+        require!(
+            vault_token_account.amount >= self.current_balance,
+            WagerError::VaultBalanceMismatch
+        );
+        Ok(vault_token_account.amount - self.current_balance)
+    }
+}
+
+// create_game_session_handler:
+//     vault_state.game_session = game_session.key();
+//     vault_state.expected_total = bet_amount
+//         .checked_mul(players_per_team as u64 * 2)
+//         .ok_or(WagerError::ArithmeticOverflow)?;
+//     vault_state.is_active = true;
+// join_user_handler:     vault_state.record_deposit(session_bet, true)?       after the transfer in
+// pay_to_spawn_handler:  vault_state.record_deposit(spawn_cost, false)?       after the transfer in
+//                        (both through book_deposit since ENH-034)
+// distribute / refund:   let surplus = vault_state.reconcile(&vault_token_account)?;
+//                        surplus -> treasury_token_account                    before any transfer out,
+//                        vault_state.record_withdrawal(amount)?               per transfer out,
+//                        vault_state.is_active = false                        once current_balance == 0
+// Case: 50 extra tokens transferred directly to the vault ATA -> the 50 go to
+// the treasury and the winners get exactly the recorded pot, so a donation
+// can't brick the session. 50 tokens missing -> VaultBalanceMismatch.
+
 
 //FC-005:Vault Balance Reconciliation Missing
 
@@ -88,10 +180,152 @@ pub fn distribute_all_winnings_handler(/* ... */) -> Result<()> {
     let remaining_balance = ctx.accounts.vault_token_account.amount;
     require!(remaining_balance == 0, WagerError::VaultNotEmpty);
     
-    game_session.status = GameStatus::Completed;
+    let session_key = game_session.key();
+    game_session.transition(
+        session_key,
+        GameStatus::Completed,
+        StatusReason::AuthorityAction,
+        ctx.accounts.game_server.key(),
+        &mut ctx.accounts.mint_stats,
+    )?;
     Ok(())
 }
 
+// WITH PROTOCOL FEE: take config.protocol_fee_bps into the treasury first,
+// then split what's left. Same for distribute_pay_spawn_earnings.
+pub struct GameConfig {
+    // ...existing fields
+    pub dust_to_first_winner: bool,   // false: division remainder goes to the treasury
+}
+
+#[account(
+    mut,
+    associated_token::mint = game_session.mint,   // one treasury ATA per mint
+    associated_token::authority = treasury,   // PDA seeds = [b"treasury"]
+)]
+pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+/// Rounds down, so the pot keeps the fractional part
+pub fn protocol_fee(pot: u64, fee_bps: u16) -> Result<u64> {
+    // checked on the rate: with bps <= 10_000 the fee can never exceed the pot
+    require!(fee_bps <= 10_000, WagerError::FeeExceedsPot);
+    Ok((pot as u128 * fee_bps as u128 / 10_000) as u64)   // u128: no overflow, <= pot
+}
+// Cases (protocol_fee): (1000, 0) -> 0; (1000, 250) -> 25; (1001, 250) -> 25
+// (25.025 rounded down); (7, 250) -> 0; (1000, 10_000) -> 1000;
+// (0, 10_001) and (1000, 10_001) -> FeeExceedsPot, whatever the pot.
+
+pub fn distribute_all_winnings_handler(/* ... */) -> Result<()> {
+    let vault_balance = ctx.accounts.vault_token_account.amount;
+    let fee = protocol_fee(vault_balance, ctx.accounts.game_config.protocol_fee_bps)?;
+    if fee > 0 {
+        anchor_spl::token::transfer(/* vault -> treasury_token_account */, fee)?;
+    }
+
+    let pot = vault_balance - fee;
+    let amount_per_winner = pot / players_per_team as u64;
+    // remainder must leave the vault too or the empty check below fails
+    let dust = pot % players_per_team as u64;
+    let dust_to_first_winner = ctx.accounts.game_config.dust_to_first_winner;
+
+    for i in 0..players_per_team {
+        let amount = if i == 0 && dust_to_first_winner {
+            amount_per_winner + dust
+        } else {
+            amount_per_winner
+        };
+        anchor_spl::token::transfer(/* ... */, amount)?;
+    }
+    if dust > 0 && !dust_to_first_winner {
+        anchor_spl::token::transfer(/* vault -> treasury_token_account */, dust)?;
+    }
+
+    ctx.accounts.vault_token_account.reload()?;
+    require!(ctx.accounts.vault_token_account.amount == 0, WagerError::VaultNotEmpty);
+
+    let session_key = game_session.key();
+    game_session.transition(
+        session_key,
+        GameStatus::Completed,
+        StatusReason::AuthorityAction,
+        ctx.accounts.game_server.key(),
+        &mut ctx.accounts.mint_stats,
+    )?;
+    Ok(())
+}
+// Cases: 0 bps (no fee transfer, pot 1000 / 5 = 200 each), 250 bps (pot 1000:
+// fee 25, winners 195 each, dust 0), pot 1001 at 250 bps (fee 25, 976 / 5 =
+// 195 each, dust 1 to treasury), 10_001 bps rejected with FeeExceedsPot.
+// Dust: pot 1001 across 3 winners at 0 bps -> 333 each, 2 left over; the
+// treasury gets 2 (default) or the first winner gets 335 with
+// GameConfig.dust_to_first_winner = true. Either way the vault reloads to 0.
+
+// Getting fees back out of the treasury (admin only)
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ WagerError::UnauthorizedConfigUpdate)]
+    pub game_config: Account<'info, GameConfig>,
+    pub admin: Signer<'info>,
+
+    /// CHECK: signer PDA only
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,   // which per-mint treasury to withdraw from
+
+    #[account(mut, associated_token::mint = mint, associated_token::authority = treasury)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, constraint = destination.mint == treasury_token_account.mint @ WagerError::InvalidMint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub admin: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+/// amount == 0 withdraws the full balance
+pub fn withdraw_treasury_handler(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    // the outgoing admin can't drain the treasury mid-handover
+    require!(ctx.accounts.game_config.pending_admin.is_none(), WagerError::AdminTransferPending);
+
+    let balance = ctx.accounts.treasury_token_account.amount;
+    let amount = if amount == 0 { balance } else { amount };
+    require!(amount <= balance, WagerError::InsufficientFunds);
+
+    let bump = ctx.bumps.treasury;
+    // checked transfer: works for Token-2022 mints too (ENH-004)
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.treasury_token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.treasury.to_account_info(),
+            },
+            &[&[b"treasury", &[bump]]],
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    emit!(TreasuryWithdrawn {
+        admin: ctx.accounts.admin.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+    Ok(())
+}
+// Case: two completed games at 250 bps on 1000 pots leave 50 in the treasury;
+// withdraw_treasury_handler(0) moves all 50 in one call. Non-admin signer and a
+// pending admin transfer are both rejected.
+
 //FC-006:Missing Duplicate Player Check
 
 
@@ -111,6 +345,30 @@ pub fn join_user_handler(...) -> Result<()> {
     // ... rest of function safely
 }
 
+// With Option slots (FL-003) get_all_players() returns occupied slots only, so
+// the check covers both teams and can't match an empty slot. Servers can also
+// be kept out of their own lobbies:
+pub struct GameConfig {
+    // ...existing fields
+    pub servers_cannot_play: bool,
+}
+
+pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: SessionId, team_side: TeamSide) -> Result<()> {
+    let game_session = &mut ctx.accounts.game_session;
+    let player = ctx.accounts.user.key();
+
+    require!(
+        !game_session.get_all_players().contains(&player),   // same team or the other one
+        WagerError::PlayerAlreadyInGame
+    );
+    if ctx.accounts.game_config.servers_cannot_play {
+        require!(player != game_session.authority, WagerError::ServerCannotPlay);
+    }
+    // ... rest of function
+}
+// Cases: join A then B -> PlayerAlreadyInGame; join A twice -> PlayerAlreadyInGame;
+// authority joins its own session with servers_cannot_play -> ServerCannotPlay.
+
 //FC-007:Code Struct Space Implementation
 
 // SECURE: Use Anchor's automatic space calculation
@@ -161,13 +419,43 @@ impl InitSpace for GameStatus {
     init,
     payer = game_server,
     space = 8 + GameSession::INIT_SPACE,  // Reliable calculation
-    seeds = [b"game_session", session_id.as_bytes()],
+    seeds = [b"game_session", game_server.key().as_ref(), session_id.as_ref()],
     bump
 )]
 pub game_session: Account<'info, GameSession>,
 
+// Applied to every account: no hand-written `space = 8 + 4 + 10 + ...` left.
 
+#[account]
+#[derive(InitSpace)]
+pub struct VaultState { /* FC-004 fields unchanged */ }
 
+#[account]
+#[derive(InitSpace)]
+pub struct RefundState { /* FH-007 */ }
+
+#[account]
+#[derive(InitSpace)]
+pub struct GameConfig {
+    // ...fields from FIN-002 and the fixes above
+    #[max_len(MAX_AUTHORIZED_SERVERS)]
+    pub authorized_servers: Vec<ServerEntry>,
+}
 
+// GameMode (FM-008) and TeamSide (FH-003) derive InitSpace where they're
+// declared; a hand impl here as well would be a conflicting impl.
 
+// Every `space =` becomes 8 + T::INIT_SPACE:
+//     game_session   8 + GameSession::INIT_SPACE   (less the unused 5v5 roster bytes, FM-002)
+//     vault_state    8 + VaultState::INIT_SPACE
+//     refund_state   8 + RefundState::INIT_SPACE
+//     game_config    8 + GameConfig::INIT_SPACE
+//     settlement     8 + Settlement::INIT_SPACE
 
+// Drift check, one per account: build the largest possible instance
+// (session_id at 10 bytes, every Option Some, every Vec at max_len), borsh
+// serialize it and assert len <= T::INIT_SPACE:
+//     let bytes = maximal_game_session().try_to_vec().unwrap();
+//     assert!(bytes.len() <= GameSession::INIT_SPACE);
+// A field added without max_len, or a hand-sized enum that grew a payload,
+// fails here instead of at `init` on mainnet.
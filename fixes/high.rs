@@ -12,6 +12,39 @@ pub fn add_spawns(&mut self, team: u8, player_index: usize) -> Result<()> {
     Ok(())
 }
 
+// BETTER: replace add_spawns (deleted, no shim - it's internal, not part of the
+// IDL) with an amount-taking version bounded by the mode and the config cap
+impl GameSession {
+    pub fn add_spawns_amount(
+        &mut self,
+        side: TeamSide,
+        player_index: usize,
+        amount: u16,
+        max_spawns_per_player: u8,
+    ) -> Result<()> {
+        // occupied range for this mode, not the 5-slot array
+        require!(
+            player_index < self.game_mode.players_per_team(),
+            WagerError::InvalidPlayerIndex
+        );
+        if amount == 0 {
+            return Ok(());   // initial_spawn_count 0 is a valid config; nothing to add
+        }
+
+        let spawns = &mut self.team_mut(side).player_spawns[player_index];
+        let new_total = spawns.checked_add(amount).ok_or(WagerError::ArithmeticOverflow)?;
+        require!(new_total <= max_spawns_per_player as u16, WagerError::TooManySpawns);
+        *spawns = new_total;
+        Ok(())
+    }
+}
+
+// pay_to_spawn_handler: add_spawns_amount(side, i, config.spawn_purchase_count as u16, config.max_spawns_per_player)
+// join_user_handler:    add_spawns_amount(side, i, config.initial_spawn_count as u16, config.max_spawns_per_player)
+// Cases: index 5 on a 5v5 (and index 1 on a 1v1) -> InvalidPlayerIndex; amount 0
+// (join with initial_spawn_count 0) -> Ok and spawns unchanged; spawns at u16::MAX - 1 plus 2 -> ArithmeticOverflow,
+// never a wrap (the cap check can't run on a wrapped value).
+
 
 // FH-002: No Spawn Limit Validation + Economic Model Flaw
 
@@ -45,11 +78,32 @@ pub fn pay_to_spawn_handler(...) -> Result<()> {
     Ok(())
 }
 
+// With GameConfig: the cap and the pack size come from config, and the check
+// runs before the transfer so a rejected purchase never moves tokens
+pub fn pay_to_spawn_handler(ctx: Context<PayToSpawn>, _session_id: SessionId, team_side: TeamSide) -> Result<()> {
+    let config = &ctx.accounts.game_config;
+    let game_session = &mut ctx.accounts.game_session;
+    let player_index = game_session.get_player_index(team_side, ctx.accounts.user.key())?;
+
+    let purchase = config.spawn_purchase_count as u16;
+    let max = config.max_spawns_per_player as u16;
+    let current = game_session.team(team_side).player_spawns[player_index];
+    require!(current + purchase <= max, WagerError::TooManySpawns);   // u8 + u8 into u16: no overflow
+
+    anchor_spl::token::transfer(/* ... */, spawn_cost)?;
+
+    // cap enforced again inside, so an internal caller can't push past it
+    game_session.add_spawns_amount(team_side, player_index, purchase, config.max_spawns_per_player)?;
+    Ok(())
+}
+// Case: max 20, pack 10, initial 0 -> two purchases reach exactly 20; the third
+// fails with TooManySpawns and the vault balance is unchanged.
+
 // Fix reward calculation - only reward kills, not spawns
 pub fn distribute_pay_spawn_earnings(/* ... */) -> Result<()> {
     for player in players {
         let player_kills = game_session.get_player_kills(player)?;  // Only kills
-        let earnings = player_kills as u64 * game_session.session_bet / 10;
+        let earnings = math::mul_div(player_kills as u64, game_session.session_bet, 10)?;
         
         if earnings > 0 {
             anchor_spl::token::transfer(/* ... */, earnings)?;
@@ -62,13 +116,13 @@ pub fn distribute_pay_spawn_earnings(/* ... */) -> Result<()> {
 
 
 // SECURE: Use enum for type safety
-#[derive(Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AnchorSerialize, AnchorDeserialize, InitSpace)]
 pub enum TeamSide {
     A = 0,
     B = 1,
 }
 
-pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: String, team_side: TeamSide) -> Result<()> {
+pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: SessionId, team_side: TeamSide) -> Result<()> {
     // No validation needed - enum guarantees valid values
     let empty_index = game_session.get_player_empty_slot(team_side)?;
     // ... rest of function
@@ -81,10 +135,77 @@ pub fn get_player_empty_slot(&self, team_side: TeamSide) -> Result<usize> {
     }
 }
 
+// Apply it everywhere: one accessor pair instead of a 0/1/_ match per method
+impl TeamSide {
+    pub fn opponent(self) -> TeamSide {
+        match self {
+            TeamSide::A => TeamSide::B,
+            TeamSide::B => TeamSide::A,
+        }
+    }
+}
+
+impl GameSession {
+    pub fn team(&self, side: TeamSide) -> &Team {
+        match side {
+            TeamSide::A => &self.team_a,
+            TeamSide::B => &self.team_b,
+        }
+    }
+
+    pub fn team_mut(&mut self, side: TeamSide) -> &mut Team {
+        match side {
+            TeamSide::A => &mut self.team_a,
+            TeamSide::B => &mut self.team_b,
+        }
+    }
+
+    pub fn get_player_empty_slot(&self, side: TeamSide) -> Result<usize> {
+        let player_count = self.game_mode.players_per_team();
+        self.team(side).get_empty_slot(player_count)
+    }
+
+    pub fn get_player_index(&self, side: TeamSide, player: Pubkey) -> Result<usize> {
+        self.team(side)
+            .players
+            .iter()
+            .position(|p| *p == Some(player))
+            .ok_or(error!(WagerError::PlayerNotFound))
+    }
+
+    pub fn add_kill(
+        &mut self,
+        killer_side: TeamSide,
+        killer: Pubkey,
+        victim_side: TeamSide,
+        victim: Pubkey,
+    ) -> Result<()> {
+        let killer_index = self.get_player_index(killer_side, killer)?;
+        let victim_index = self.get_player_index(victim_side, victim)?;
+
+        let kills = &mut self.team_mut(killer_side).player_kills[killer_index];
+        *kills = kills.saturating_add(1);
+
+        let spawns = &mut self.team_mut(victim_side).player_spawns[victim_index];
+        require!(*spawns > 0, WagerError::NoSpawnsRemaining);
+        *spawns -= 1;
+        Ok(())
+    }
+}
+
+// Instruction signatures take TeamSide instead of u8:
+//   join_user(session_id, team_side: TeamSide)
+//   pay_to_spawn(session_id, team_side: TeamSide)
+//   record_kill(session_id, killer_side, killer, victim_side, victim)
+// Borsh encodes TeamSide as one byte (0 / 1), same as the old u8, so
+// discriminators don't move - but any other byte now fails deserialization
+// before the handler runs. That's an IDL type change: clients regenerate, and
+// tests pass { a: {} } / { b: {} } instead of 0 / 1.
+
 // FH-004: No Game State Validation
 
 // SECURE: Add proper state validation
-pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: String, team: u8) -> Result<()> {
+pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: SessionId, team: u8) -> Result<()> {
     let game_session = &mut ctx.accounts.game_session;
     
     // Validate game state
@@ -97,7 +218,8 @@ pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: String, team: u8)
 }
 
 pub fn refund_wager_handler(/* ... */) -> Result<()> {
-    let game_session = &ctx.accounts.game_session;
+    let session_key = ctx.accounts.game_session.key();
+    let game_session = &mut ctx.accounts.game_session;
     
     // Only allow refunds for waiting games
     require!(
@@ -106,7 +228,15 @@ pub fn refund_wager_handler(/* ... */) -> Result<()> {
     );
     
     // ... rest of function
-    game_session.status = GameStatus::Refunded;  // Proper final state
+    // Proper final state
+    game_session.transition(
+        session_key,
+        GameStatus::Refunded,
+        StatusReason::AuthorityAction,
+        ctx.accounts.game_server.key(),
+        &mut ctx.accounts.mint_stats,
+    )?;
+    Ok(())
 }
 
 
@@ -127,19 +257,77 @@ pub fn distribute_all_winnings_handler(
     };
     
     // Use stored data directly - no remaining_accounts needed
-    for &winner_pubkey in winning_players {
-        if winner_pubkey == Pubkey::default() {
-            continue;  // Skip empty slots
-        }
-        
+    // (flatten skips empty slots - they're None, not a default key)
+    for winner_pubkey in winning_players.iter().flatten() {
         // Derive ATA deterministically
-        let winner_ata = get_associated_token_address(&winner_pubkey, &TOKEN_ID);
+        let winner_ata = get_associated_token_address(winner_pubkey, &game_session.mint);
         
         // Create transfer instruction with derived accounts
         // Much simpler and safer than remaining_accounts
     }
 }
 
+// Full version: remaining_accounts still carries (wallet, token account) per
+// winner slot, but the wallet must be the stored player and the token account
+// must BE its derived ATA. Missing ATAs are created with game_server paying.
+pub fn distribute_all_winnings_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DistributeWinnings<'info>>,
+    _session_id: SessionId,
+    winning_team: TeamSide,
+) -> Result<()> {
+    let game_session = &ctx.accounts.game_session;
+    let players_per_team = game_session.game_mode.players_per_team();
+    let mint = game_session.mint;
+    let winners = &game_session.team(winning_team).players[0..players_per_team];
+
+    require!(
+        ctx.remaining_accounts.len() == 2 * players_per_team,
+        WagerError::InvalidRemainingAccounts
+    );
+
+    for (winner, pair) in winners.iter().zip(ctx.remaining_accounts.chunks(2)) {
+        let Some(winner) = winner else { continue };
+        let (winner_wallet, winner_ata) = (&pair[0], &pair[1]);
+
+        require_keys_eq!(winner_wallet.key(), *winner, WagerError::InvalidWinnerTokenAccount);
+
+        require_keys_eq!(
+            winner_ata.key(),
+            get_associated_token_address(winner, &mint),
+            WagerError::InvalidWinnerTokenAccount
+        );
+
+        if winner_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: ctx.accounts.game_server.to_account_info(),
+                    associated_token: winner_ata.clone(),
+                    authority: winner_wallet.clone(),   // owner only, no signature needed
+                    mint: ctx.accounts.mint.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ))?;
+        } else {
+            // Address matches, so owner/mint can only differ if the account was
+            // reassigned (SetAuthority) - still refuse it
+            let account = TokenAccount::try_deserialize(&mut &winner_ata.data.borrow()[..])?;
+            require!(
+                account.owner == *winner && account.mint == mint,
+                WagerError::InvalidWinnerTokenAccount
+            );
+        }
+
+        // ... vault-signed transfer to winner_ata
+    }
+    // ... dust + vault-empty check (FC-005)
+    Ok(())
+}
+// DistributeWinnings gains mint, associated_token_program, system_program and
+// `#[account(mut)] game_server`. Case: attacker passes their own ATA in slot 1
+// -> InvalidWinnerTokenAccount and nobody is paid (whole tx reverts).
+
 // FH-006: No Authority Validation
 
 // SECURE: Add consistent authority validation
@@ -172,6 +360,35 @@ pub struct RecordKill<'info> {
 
 // or create a central config account with authority info and use that for easy validation
 
+// Applied to every server-signed instruction. The constraint runs before the
+// handler, so the runtime require! in record_kill_handler above is deleted.
+#[derive(Accounts)]
+#[instruction(session_id: SessionId)]
+pub struct DistributeWinnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_session", game_session.authority.as_ref(), session_id.as_ref()],
+        bump = game_session.bump,
+        constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedOperation,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+    // ... vault, vault_token_account, token_program
+}
+
+// Same `constraint = ... @ WagerError::UnauthorizedOperation` line on:
+//     RecordKill (above), DistributePaySpawn, RefundWager,
+//     StartGame, KickPlayer, DeclareDraw, FinalizeGame
+// CloseGameSession uses has_one = authority with the same error.
+// Not on player-signed instructions (JoinUser, PayToSpawn, LeaveGame,
+// ClaimWinnings, ClaimRefund, RaiseDispute) - those check membership instead.
+
+// Negative tests sign each of those with a fresh keypair and assert
+// error.error.errorCode.code == "UnauthorizedOperation" - not just "a
+// ConstraintRaw failed" - so a missing @ error mapping shows up as a failure.
+
 
 // FH-007: Double Spend Vulnerability
 // SECURE: Add refund state tracking
@@ -194,11 +411,8 @@ pub fn refund_wager_handler(
     
     let players = game_session.get_all_players();
 
+    // occupied slots only - get_all_players never yields an empty slot
     for player in players {
-        if player == Pubkey::default() {
-            continue;
-        }
-
         // Check if player already refunded
         require!(
             !refund_state.players_refunded.contains(&player),
@@ -213,15 +427,109 @@ pub fn refund_wager_handler(
         // Track refunded player
         // get the refunded_state and add the player
         // refund_state.players_refunded; 
-        refund_state.total_refunded += refund;
+        refund_state.total_refunded = refund_state
+            .total_refunded
+            .checked_add(refund)
+            .ok_or(WagerError::ArithmeticOverflow)?;
     }
 
     // Mark refund as completed
     refund_state.refund_completed = true;
-    game_session.status = GameStatus::Refunded;
+    let session_key = game_session.key();
+    game_session.transition(
+        session_key,
+        GameStatus::Refunded,
+        StatusReason::AuthorityAction,
+        ctx.accounts.game_server.key(),
+        &mut ctx.accounts.mint_stats,
+    )?;
     Ok(())
 }
 
+// Idempotent version: RefundState is its own PDA, created on the first call,
+// and already-refunded players are skipped instead of aborting the whole
+// call - players who pulled their own refund (claim_refund) don't block the rest.
+#[account]
+#[derive(InitSpace)]
+pub struct RefundState {
+    pub game_session: Pubkey,
+    pub players_refunded: [Option<Pubkey>; 10],
+    pub total_refunded: u64,
+    pub refund_completed: bool,
+    pub bump: u8,
+}
+
+impl RefundState {
+    pub fn is_refunded(&self, player: &Pubkey) -> bool {
+        self.players_refunded.contains(&Some(*player))
+    }
+
+    pub fn mark_refunded(&mut self, player: Pubkey) -> Result<()> {
+        let slot = self
+            .players_refunded
+            .iter()
+            .position(Option::is_none)
+            .ok_or(WagerError::RefundListFull)?;
+        self.players_refunded[slot] = Some(player);
+        Ok(())
+    }
+}
+
+#[account(
+    init_if_needed,
+    payer = game_server,
+    space = 8 + RefundState::INIT_SPACE,
+    seeds = [b"refund", game_session.key().as_ref()],
+    bump
+)]
+pub refund_state: Account<'info, RefundState>,
+
+pub fn refund_wager_handler(ctx: Context<RefundWager>, _session_id: SessionId) -> Result<()> {
+    let session_key = ctx.accounts.game_session.key();
+    let game_session = &mut ctx.accounts.game_session;
+    let refund_state = &mut ctx.accounts.refund_state;
+
+    require!(!refund_state.refund_completed, WagerError::RefundAlreadyCompleted);
+    if refund_state.game_session == Pubkey::default() {
+        // first call: init_if_needed just created it
+        refund_state.game_session = game_session.key();
+        refund_state.bump = ctx.bumps.refund_state;
+    }
+
+    let players = game_session.get_all_players();   // occupied slots only
+    for player in players.iter() {
+        if refund_state.is_refunded(player) {
+            continue;
+        }
+        let (side, index) = game_session.locate(*player)?;
+        // entry + every spawn purchase, the same amount claim_refund pays
+        let refund = game_session.team(side).player_total_paid[index];
+        // Record before the transfer; a failed transfer reverts both
+        refund_state.mark_refunded(*player)?;
+        refund_state.total_refunded = refund_state
+            .total_refunded
+            .checked_add(refund)
+            .ok_or(WagerError::ArithmeticOverflow)?;
+
+        anchor_spl::token::transfer(/* vault -> player's derived ATA */, refund)?;
+    }
+
+    if players.iter().all(|p| refund_state.is_refunded(p)) {
+        refund_state.refund_completed = true;
+        game_session.transition(
+            session_key,
+            GameStatus::Refunded,
+            StatusReason::AuthorityAction,
+            ctx.accounts.game_server.key(),
+            &mut ctx.accounts.mint_stats,
+        )?;
+    }
+    Ok(())
+}
+// Case: 2 players at bet 100, one bought 3 spawns at 10 -> first call pays
+// 100 + 130 and completes; second call fails with RefundAlreadyCompleted and
+// the vault balance is unchanged.
+
 
 
 // FH-008: Kill Recording Missing Validation
@@ -264,6 +572,58 @@ pub fn record_kill_handler(
     Ok(())
 }
 
+// With TeamSide and Option slots: membership comes from the stored arrays
+// (position_of), not the caller's claim, and every rejection has its own error
+pub struct GameSession {
+    // ...existing fields
+    pub friendly_fire: bool,   // chosen at create; off by default
+}
+
+pub fn record_kill_handler(
+    ctx: Context<RecordKill>,
+    _session_id: SessionId,
+    killer_team: TeamSide,
+    killer: Pubkey,
+    victim_team: TeamSide,
+    victim: Pubkey,
+) -> Result<()> {
+    let game_session = &mut ctx.accounts.game_session;
+
+    require!(game_session.status == GameStatus::InProgress, WagerError::GameNotInProgress);
+    require!(killer != victim, WagerError::SelfKillNotAllowed);
+    require!(
+        killer_team != victim_team || game_session.friendly_fire,
+        WagerError::FriendlyFireNotAllowed
+    );
+
+    let killer_index = game_session
+        .team(killer_team)
+        .position_of(&killer)
+        .ok_or(WagerError::KillerNotInTeam)?;
+    let victim_index = game_session
+        .team(victim_team)
+        .position_of(&victim)
+        .ok_or(WagerError::VictimNotInTeam)?;
+
+    // Spawns only move under PayToSpawn / Elimination (SpawnPolicy, ENH-041);
+    // a winner-takes-all victim has none and the kill still counts
+    let outcome = game_session.apply_death(victim_team, victim_index)?;
+
+    let killer_kills = &mut game_session.team_mut(killer_team).player_kills[killer_index];
+    *killer_kills = killer_kills.saturating_add(1);
+
+    if let KillOutcome::TeamEliminated(side) = outcome {
+        emit!(TeamEliminated { session: game_session.key(), side });
+    }
+    Ok(())
+}
+// Rejections: not InProgress -> GameNotInProgress; killer == victim ->
+// SelfKillNotAllowed; same team without friendly_fire -> FriendlyFireNotAllowed;
+// killer on the other team -> KillerNotInTeam; victim likewise ->
+// VictimNotInTeam; pay-to-spawn victim at 0 spawns -> NoSpawnsRemaining. Happy
+// path: killer kills +1, victim spawns -1 in pay-to-spawn, spawns untouched in
+// winner-takes-all; the last spawn on an Elimination team emits TeamEliminated.
+
 
 // FH-009: Vault Seed Security Weakness
 
@@ -288,4 +648,149 @@ seeds = [
     session_id.as_bytes()
 ],
 
-// This ensures each game has unique vault regardless of session_id collisions
\ No newline at end of file
+// This ensures each game has unique vault regardless of session_id collisions
+
+// Applied: the vault and its token account are seeded by the game_session key
+// alone (already unique per session), and the session PDA itself includes the
+// authority - otherwise two servers picking the same session_id still collide
+// one level up, at the game_session account.
+#[derive(Accounts)]
+#[instruction(session_id: SessionId)]
+pub struct CreateGameSession<'info> {
+    #[account(
+        init,
+        payer = game_server,
+        space = 8 + GameSession::INIT_SPACE,
+        seeds = [b"game_session", game_server.key().as_ref(), session_id.as_ref()],
+        bump
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    // FC-004 VaultState at the vault PDA: it is also the token authority
+    #[account(
+        init,
+        payer = game_server,
+        space = 8 + VaultState::INIT_SPACE,
+        seeds = [b"vault", game_session.key().as_ref()],
+        bump
+    )]
+    pub vault_state: Account<'info, VaultState>,
+
+    #[account(
+        init,
+        payer = game_server,
+        seeds = [b"vault_token", game_session.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_state,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    // ... game_server, mint, programs
+}
+
+// create_game_session_handler stores all three bumps:
+//     game_session.bump = ctx.bumps.game_session;
+//     game_session.vault_bump = ctx.bumps.vault_state;
+//     game_session.vault_token_bump = ctx.bumps.vault_token_account;
+// Every other instruction re-derives with
+//     seeds = [b"game_session", game_session.authority.as_ref(), session_id.as_ref()]
+//     seeds = [b"vault", game_session.key().as_ref()], bump = game_session.vault_bump
+//     seeds = [b"vault_token", game_session.key().as_ref()], bump = game_session.vault_token_bump
+// The vault token account is this PDA everywhere, never the vault's ATA.
+
+/// What is leaving the vault, for the books
+pub enum VaultOutflow {
+    Payout(u64),    // to a player: winnings, refunds, leave/kick
+    Fee(u64),       // protocol fee and split dust, to the treasury
+    Surplus(u64),   // tokens sent straight to the vault (FC-004 reconcile);
+                    // never booked as a deposit, so nothing to release
+}
+
+/// The vault side of an outflow: the token account, the VaultState PDA that
+/// owns it, and the stats that have to move with it
+pub struct VaultAccounts<'a, 'info> {
+    pub token_program: &'a Interface<'info, TokenInterface>,
+    pub mint: &'a InterfaceAccount<'info, Mint>,   // transfer_checked / burn (session mint)
+    pub vault_state: &'a mut Account<'info, VaultState>,
+    pub vault_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    pub mint_stats: &'a mut Account<'info, MintStats>,
+    pub game_config: &'a GameConfig,
+    pub protocol_stats: &'a mut Account<'info, ProtocolStats>,
+    pub game_session: Pubkey,
+    pub vault_bump: u8,
+}
+
+/// Deposit side of the books: right after every transfer into the vault.
+/// Fails with ProtocolCapacityReached past the TVL cap (ENH-034), which
+/// reverts the transfer with it.
+pub fn book_deposit(vault: &mut VaultAccounts<'_, '_>, amount: u64, new_player: bool) -> Result<()> {
+    apply_locked_change(vault.game_config, vault.protocol_stats, vault.mint_stats, LockedChange::Deposit(amount))?;
+    vault.vault_state.record_deposit(amount, new_player)
+}
+
+/// Every transfer out of the vault (distribution, refunds, leave/kick) signs
+/// and is booked here, so VaultState and MintStats never lag the token account
+pub fn refund_from_vault(
+    vault: &mut VaultAccounts<'_, '_>,
+    to: &InterfaceAccount<'_, TokenAccount>,
+    outflow: VaultOutflow,
+) -> Result<()> {
+    let amount = match outflow {
+        VaultOutflow::Payout(amount) | VaultOutflow::Fee(amount) | VaultOutflow::Surplus(amount) => amount,
+    };
+    // checked against the mint, so Token-2022 mints (fees, hooks) move correctly
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            vault.token_program.to_account_info(),
+            TransferChecked {
+                from: vault.vault_token_account.to_account_info(),
+                mint: vault.mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: vault.vault_state.to_account_info(),
+            },
+            &[&[b"vault", vault.game_session.as_ref(), &[vault.vault_bump]]],
+        ),
+        amount,
+        vault.mint.decimals,
+    )?;
+    let change = match outflow {
+        VaultOutflow::Payout(amount) => LockedChange::Release { paid_out: amount, fees: 0 },
+        VaultOutflow::Fee(amount) => LockedChange::Release { paid_out: 0, fees: amount },
+        VaultOutflow::Surplus(_) => return Ok(()),
+    };
+    vault.vault_state.record_withdrawal(amount)?;
+    apply_locked_change(vault.game_config, vault.protocol_stats, vault.mint_stats, change)
+}
+
+/// The burn twin of refund_from_vault (ENH-038): the burned share of the fee
+/// leaves the vault and the locked value like any other fee.
+pub fn burn_from_vault(vault: &mut VaultAccounts<'_, '_>, amount: u64) -> Result<()> {
+    token_interface::burn(
+        CpiContext::new_with_signer(
+            vault.token_program.to_account_info(),
+            Burn {
+                mint: vault.mint.to_account_info(),
+                from: vault.vault_token_account.to_account_info(),
+                authority: vault.vault_state.to_account_info(),
+            },
+            &[&[b"vault", vault.game_session.as_ref(), &[vault.vault_bump]]],
+        ),
+        amount,
+    )?;
+    vault.vault_state.record_withdrawal(amount)?;
+    apply_locked_change(
+        vault.game_config,
+        vault.protocol_stats,
+        vault.mint_stats,
+        LockedChange::Release { paid_out: 0, fees: amount },
+    )
+}
+// Every context that calls it takes vault_state (seeds [b"vault", game_session],
+// bump = game_session.vault_bump), mint_stats and protocol_stats as `mut`, and
+//     #[account(address = game_session.mint @ WagerError::InvalidMint)]
+//     pub mint: InterfaceAccount<'info, Mint>, Case: leave, then
+// reconcile -> surplus 0, and invariants::vault_matches_liabilities holds.
+// Joins transfer IN with the player as authority, so only the vault_token_account
+// address changes for them. Case: servers S1 and S2 both create "match1" ->
+// two sessions, two vaults; joining, distributing and refunding on one leaves
+// the other's balance untouched.
\ No newline at end of file
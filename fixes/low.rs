@@ -14,11 +14,9 @@ pub fn create_game_session_handler(/* ... */) -> Result<()> {
     game_session.status = GameStatus::WaitingForPlayers;
     game_session.created_at = clock.unix_timestamp;
     game_session.bump = ctx.bumps.game_session;
-    game_session.vault_bump = ctx.bumps.vault;
+    game_session.vault_bump = ctx.bumps.vault_state;
 
-    // Keep only essential event logging
-    msg!("Game created: {}", session_id);
-    
+    // No msg! at all: the GameCreated event below is what indexers read
     Ok(())
 }
 
@@ -46,6 +44,136 @@ pub fn distribute_pay_spawn_earnings(/* ... */) -> Result<()> {
 }
 ```
 
+// BETTER: no amount formatting on-chain at all
+// "Refunded 1.5 tokens" costs compute and assumes 9 decimals, which is wrong
+// for per-session mints. Amounts go into typed events as raw u64 plus the
+// session's decimals, and the handlers log nothing else.
+```rust
+pub struct GameSession {
+    // ...existing fields
+    pub decimals: u8,   // copied from the mint at creation
+}
+
+#[event]
+pub struct PlayerRefunded {
+    pub session_id: String,
+    pub player: Pubkey,
+    pub amount: u64,    // raw base units
+    pub decimals: u8,
+}
+
+pub fn refund_wager_handler(/* ... */) -> Result<()> {
+    // ...
+    emit!(PlayerRefunded {
+        session_id: game_session.get_session_id_string(),
+        player,
+        amount: refund,
+        decimals: game_session.decimals,
+    });
+    Ok(())
+}
+
+// client/types: the one place amounts become strings
+pub fn format_amount(raw: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return raw.to_string();
+    }
+    let scale = 10u64.pow(decimals as u32);
+    let whole = raw / scale;
+    let frac = raw % scale;
+    // full precision, trailing zeros trimmed: 1_500_000 @ 6 => "1.5"
+    let frac = format!("{:0width$}", frac, width = decimals as usize);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() { whole.to_string() } else { format!("{}.{}", whole, frac) }
+}
+
+// format_amount(15, 0)            == "15"
+// format_amount(1_500_000, 6)     == "1.5"
+// format_amount(1, 6)             == "0.000001"
+// format_amount(1_000_000_000, 9) == "1"
+// format_amount(123_456_789, 9)   == "0.123456789"
+```
+
+// Lifecycle events: indexers read these instead of parsing msg! logs
+```rust
+#[event]
+pub struct GameCreated {
+    pub session_id: String,
+    pub authority: Pubkey,
+    pub bet: u64,
+    pub mode: GameMode,
+}
+
+#[event]
+pub struct PlayerJoined {
+    pub session_id: String,
+    pub player: Pubkey,
+    pub team_side: TeamSide,
+    pub slot: u8,
+}
+
+#[event]
+pub struct KillRecorded {
+    pub session_id: String,
+    pub killer: Pubkey,
+    pub victim: Pubkey,
+    pub killer_kills: u16,
+    pub victim_spawns_remaining: u16,
+}
+
+#[event]
+pub struct WinningsDistributed {
+    pub session_id: String,
+    pub winning_team: TeamSide,
+    pub total_paid: u64,
+}
+
+// Handler-level total; per-player amounts are in PlayerRefunded above
+#[event]
+pub struct WagerRefunded {
+    pub session_id: String,
+    pub total_refunded: u64,
+}
+
+pub fn create_game_session_handler(/* ... */) -> Result<()> {
+    // ... (no msg! calls)
+    emit!(GameCreated {
+        session_id: game_session.get_session_id_string(),
+        authority: game_session.authority,
+        bet: bet_amount,
+        mode: game_mode,
+    });
+    Ok(())
+}
+
+pub fn join_user_handler(/* ... */) -> Result<()> {
+    // ...
+    emit!(PlayerJoined {
+        session_id: game_session.get_session_id_string(),
+        player: user.key(),
+        team_side,
+        slot: empty_index as u8,
+    });
+    Ok(())
+}
+
+pub fn record_kill_handler(/* ... */) -> Result<()> {
+    game_session.add_kill(killer_team, killer, victim_team, victim)?;
+    emit!(KillRecorded {
+        session_id: game_session.get_session_id_string(),
+        killer,
+        victim,
+        killer_kills: game_session.get_player_kills(killer)?,
+        victim_spawns_remaining: game_session.get_player_spawns(victim)?,
+    });
+    Ok(())
+}
+
+// distribute_all_winnings_handler: emit!(WinningsDistributed { .., total_paid })
+// refund_wager_handler:            emit!(WagerRefunded { .., total_refunded })
+// Every debug msg! ("Team A players: {:?}", "Distributing to {} ...") is deleted.
+```
+
 // ## FL-002: String vs Array for Session ID
 // OPTION 1: Keep String but add proper validation (Recommended)
 ```rust
@@ -59,7 +187,7 @@ pub struct GameSession {
 // Add proper runtime validation
 pub fn create_game_session_handler(
     ctx: Context<CreateGameSession>,
-    session_id: String,
+    session_id: SessionId,
     bet_amount: u64,
     game_mode: GameMode,
 ) -> Result<()> {
@@ -96,6 +224,62 @@ impl GameSession {
 }
 ```
 
+// Option 2 end to end: the instruction argument and the PDA seed are the same
+// zero-padded [u8; 10], so the seed can't differ from what's stored
+```rust
+pub type SessionId = [u8; 10];
+
+/// Same rules as validation::validate_session_id (FC-003), on the padded form:
+/// non-empty, [A-Za-z0-9_-] up to the first zero, zeros only after it
+pub fn validate_session_id_bytes(id: &SessionId) -> Result<()> {
+    let len = id.iter().position(|&b| b == 0).unwrap_or(10);
+    require!(len > 0, WagerError::SessionIdEmpty);
+    require!(
+        id[..len].iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'-'),
+        WagerError::SessionIdInvalidChars
+    );
+    require!(id[len..].iter().all(|&b| b == 0), WagerError::SessionIdInvalidChars);
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(session_id: SessionId)]
+pub struct CreateGameSession<'info> {
+    #[account(
+        init,
+        payer = game_server,
+        space = 8 + GameSession::INIT_SPACE,
+        seeds = [b"game_session", game_server.key().as_ref(), session_id.as_ref()],
+        bump
+    )]
+    pub game_session: Account<'info, GameSession>,
+    // ...
+}
+// Every other context takes `#[instruction(session_id: SessionId)]` and seeds
+// with `session_id.as_ref()`; every handler takes `session_id: SessionId`.
+// Only create validates: any other id derives a different PDA and fails the
+// seeds check. Events keep `session_id: String` from get_session_id_string().
+
+pub fn create_game_session_handler(
+    ctx: Context<CreateGameSession>,
+    session_id: SessionId,
+    bet_amount: u64,
+    game_mode: GameMode,
+) -> Result<()> {
+    validate_session_id_bytes(&session_id)?;
+    ctx.accounts.game_session.session_id = session_id;
+    // ... rest of function
+}
+```
+// The 11-byte case can no longer reach the program (Borsh reads exactly 10), so
+// the length check lives in the client helper that builds the argument:
+//     sessionIdBytes("match_0001") -> 10 bytes, used as-is
+//     sessionIdBytes("game1")      -> "game1" + 5 zero bytes
+//     sessionIdBytes("match_00011") -> throws before sending
+// Tests switch from passing "game1" to sessionIdBytes("game1"), and from
+// Buffer.from(id) to the padded bytes when deriving PDAs. Log lines and events
+// use get_session_id_string().
+
 // ## FL-003: Option Usage for Clarity
 // CLEAR: Use Option for better semantics
 ```rust
@@ -139,6 +323,46 @@ impl Team {
 }
 ```
 
+// Carrying Option through the rest of the code (no Pubkey::default() left anywhere)
+```rust
+impl Team {
+    pub fn position_of(&self, player: &Pubkey) -> Option<usize> {
+        self.players.iter().position(|p| p.as_ref() == Some(player))
+    }
+}
+
+impl GameSession {
+    /// Only occupied slots - callers no longer skip default keys themselves
+    pub fn get_all_players(&self) -> Vec<Pubkey> {
+        self.team_a.players.iter()
+            .chain(self.team_b.players.iter())
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    pub fn get_player_index(&self, side: TeamSide, player: Pubkey) -> Result<usize> {
+        self.team(side).position_of(&player).ok_or(error!(WagerError::PlayerNotFound))
+    }
+}
+
+pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: SessionId, team_side: TeamSide) -> Result<()> {
+    let player = ctx.accounts.user.key();
+    // A signer can't be the default key in practice, but never let one into a slot
+    require!(player != Pubkey::default(), WagerError::InvalidPlayer);
+    // ... rest of function
+}
+
+// Distribution / refund loops iterate occupied slots only:
+for (i, winner) in team.players.iter().enumerate().take(players_per_team) {
+    let Some(winner) = winner else { continue };
+    // ...
+}
+```
+// Layout change: each slot grows from 32 to 33 bytes and the Option tag sits
+// in front of every key, so old accounts don't deserialize. No migration -
+// stop creating sessions, let in-flight ones complete or refund, then deploy.
+
 // ## FIN-001: Poor Naming Conventions
 // CLEAR: Use descriptive and consistent naming
 ```rust
@@ -305,4 +529,241 @@ pub fn update_config_handler(
     ctx.accounts.game_config.set_inner(new_config);
     Ok(())
 }
-```
\ No newline at end of file
+```
+
+// SAFER: field-level updates instead of set_inner
+// set_inner(new_config) can silently clobber authorized_servers or even admin
+// when the caller only meant to change one fee. Every field is optional and
+// admin can't be changed through this path at all.
+```rust
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct UpdateConfigArgs {
+    pub min_bet_amount: Option<u64>,
+    pub max_bet_amount: Option<u64>,
+    pub initial_spawn_count: Option<u8>,
+    pub spawn_purchase_count: Option<u8>,
+    pub spawn_cost_divisor: Option<u8>,
+    pub max_spawns_per_player: Option<u8>,
+    pub reward_per_kill: Option<u64>,
+    pub max_game_duration: Option<i64>,
+    pub protocol_fee_bps: Option<u16>,
+    // no `admin`, no `authorized_servers` - they have their own instructions
+}
+
+/// Bit per field, in UpdateConfigArgs order
+pub mod config_fields {
+    pub const MIN_BET_AMOUNT: u16 = 1 << 0;
+    pub const MAX_BET_AMOUNT: u16 = 1 << 1;
+    pub const INITIAL_SPAWN_COUNT: u16 = 1 << 2;
+    pub const SPAWN_PURCHASE_COUNT: u16 = 1 << 3;
+    pub const SPAWN_COST_DIVISOR: u16 = 1 << 4;
+    pub const MAX_SPAWNS_PER_PLAYER: u16 = 1 << 5;
+    pub const REWARD_PER_KILL: u16 = 1 << 6;
+    pub const MAX_GAME_DURATION: u16 = 1 << 7;
+    pub const PROTOCOL_FEE_BPS: u16 = 1 << 8;
+    pub const UPDATE_DELAY_SECONDS: u16 = 1 << 9;   // timelock only
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub changed_fields: u16,   // config_fields bits
+}
+
+pub fn update_config_handler(ctx: Context<UpdateConfig>, args: UpdateConfigArgs) -> Result<()> {
+    require!(
+        ctx.accounts.admin.key() == ctx.accounts.game_config.admin,
+        WagerError::UnauthorizedConfigUpdate
+    );
+
+    let config = &mut ctx.accounts.game_config;
+    let mut changed = 0u16;
+
+    macro_rules! apply {
+        ($field:ident, $bit:expr) => {
+            if let Some(value) = args.$field {
+                config.$field = value;
+                changed |= $bit;
+            }
+        };
+    }
+    apply!(min_bet_amount, config_fields::MIN_BET_AMOUNT);
+    apply!(max_bet_amount, config_fields::MAX_BET_AMOUNT);
+    apply!(initial_spawn_count, config_fields::INITIAL_SPAWN_COUNT);
+    apply!(spawn_purchase_count, config_fields::SPAWN_PURCHASE_COUNT);
+    apply!(spawn_cost_divisor, config_fields::SPAWN_COST_DIVISOR);
+    apply!(max_spawns_per_player, config_fields::MAX_SPAWNS_PER_PLAYER);
+    apply!(reward_per_kill, config_fields::REWARD_PER_KILL);
+    apply!(max_game_duration, config_fields::MAX_GAME_DURATION);
+    apply!(protocol_fee_bps, config_fields::PROTOCOL_FEE_BPS);
+
+    // Validate the result, not just the inputs (a new min against the old max, ...)
+    require!(config.min_bet_amount > 0, WagerError::InvalidConfig);
+    require!(config.max_bet_amount >= config.min_bet_amount, WagerError::InvalidConfig);
+    require!(config.spawn_cost_divisor > 0, WagerError::InvalidConfig);
+    require!(config.initial_spawn_count <= config.max_spawns_per_player, WagerError::InvalidConfig);
+    require!(config.protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS, WagerError::InvalidConfig);
+    require!(config.max_game_duration > 0, WagerError::InvalidConfig);
+
+    emit!(ConfigUpdated { admin: ctx.accounts.admin.key(), changed_fields: changed });
+    Ok(())
+}
+```
+
+// SAFER: two-step admin transfer
+// A typo'd admin pubkey in a single-step handover bricks the config. The new
+// admin has to sign to accept.
+```rust
+pub struct GameConfig {
+    // ...existing fields
+    pub pending_admin: Option<Pubkey>,
+}
+
+#[derive(Accounts)]
+pub struct AdminOnly<'info> {
+    #[account(mut, seeds = [b"config"], bump, has_one = admin @ WagerError::UnauthorizedConfigUpdate)]
+    pub game_config: Account<'info, GameConfig>,
+    pub admin: Signer<'info>,
+}
+
+/// Overwrites any earlier proposal
+pub fn propose_admin_handler(ctx: Context<AdminOnly>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.game_config.pending_admin = Some(new_admin);
+    Ok(())
+}
+
+pub fn cancel_admin_transfer_handler(ctx: Context<AdminOnly>) -> Result<()> {
+    require!(ctx.accounts.game_config.pending_admin.is_some(), WagerError::NoPendingAdmin);
+    ctx.accounts.game_config.pending_admin = None;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(mut, seeds = [b"config"], bump)]
+    pub game_config: Account<'info, GameConfig>,
+    pub new_admin: Signer<'info>,
+}
+
+pub fn accept_admin_handler(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.game_config;
+    let pending = config.pending_admin.ok_or(WagerError::NoPendingAdmin)?;
+    require_keys_eq!(pending, ctx.accounts.new_admin.key(), WagerError::NotPendingAdmin);
+
+    config.admin = pending;
+    config.pending_admin = None;
+    Ok(())
+}
+```
+
+// SAFER: timelock on economic fields
+// Fees, bet bounds and spawn pricing can't change under players mid-season.
+// They go through queue -> wait update_delay_seconds -> apply; everything else
+// in UpdateConfigArgs stays immediate.
+```rust
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct PendingConfig {
+    pub min_bet_amount: Option<u64>,
+    pub max_bet_amount: Option<u64>,
+    pub spawn_purchase_count: Option<u8>,
+    pub spawn_cost_divisor: Option<u8>,
+    pub reward_per_kill: Option<u64>,
+    pub protocol_fee_bps: Option<u16>,
+    pub update_delay_seconds: Option<i64>,   // the delay itself is timelocked too
+}
+
+pub struct GameConfig {
+    // ...existing fields
+    pub update_delay_seconds: i64,
+    pub pending_update: Option<PendingConfig>,
+    pub update_effective_at: i64,
+}
+
+// A config created with a 0 delay would have no timelock at all, so the delay
+// is an initialize_config argument (FM-013) with a floor, and afterwards only
+// changes through queue -> apply below.
+pub const MIN_UPDATE_DELAY_SECONDS: i64 = 24 * 60 * 60;   // 1 day
+
+pub fn initialize_config_handler(
+    ctx: Context<InitializeConfig>,
+    // ...FM-013 arguments
+    update_delay_seconds: i64,
+) -> Result<()> {
+    // ...FM-013 checks
+    require!(update_delay_seconds >= MIN_UPDATE_DELAY_SECONDS, WagerError::InvalidConfig);
+    // ...FM-013 assignments
+    config.update_delay_seconds = update_delay_seconds;
+    Ok(())
+}
+
+// update_config_handler: economic fields are refused on the immediate path
+require!(
+    args.min_bet_amount.is_none()
+        && args.max_bet_amount.is_none()
+        && args.spawn_purchase_count.is_none()
+        && args.spawn_cost_divisor.is_none()
+        && args.reward_per_kill.is_none()
+        && args.protocol_fee_bps.is_none(),
+    WagerError::TimelockRequired
+);
+
+/// Replaces any queued update and restarts the delay
+pub fn queue_config_update_handler(ctx: Context<AdminOnly>, update: PendingConfig) -> Result<()> {
+    let config = &mut ctx.accounts.game_config;
+    config.pending_update = Some(update);
+    config.update_effective_at = Clock::get()?
+        .unix_timestamp
+        .checked_add(config.update_delay_seconds)
+        .ok_or(WagerError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ApplyConfigUpdate<'info> {
+    #[account(mut, seeds = [b"config"], bump)]
+    pub game_config: Account<'info, GameConfig>,
+    // permissionless: anyone can crank it once the delay is over
+}
+
+pub fn apply_config_update_handler(ctx: Context<ApplyConfigUpdate>) -> Result<()> {
+    let config = &mut ctx.accounts.game_config;
+    require!(
+        Clock::get()?.unix_timestamp >= config.update_effective_at,
+        WagerError::TimelockNotElapsed
+    );
+    let update = config.pending_update.take().ok_or(WagerError::NoPendingUpdate)?;
+    let mut changed = 0u16;
+
+    macro_rules! apply {
+        ($field:ident, $bit:expr) => {
+            if let Some(value) = update.$field {
+                config.$field = value;
+                changed |= $bit;
+            }
+        };
+    }
+    apply!(min_bet_amount, config_fields::MIN_BET_AMOUNT);
+    apply!(max_bet_amount, config_fields::MAX_BET_AMOUNT);
+    apply!(spawn_purchase_count, config_fields::SPAWN_PURCHASE_COUNT);
+    apply!(spawn_cost_divisor, config_fields::SPAWN_COST_DIVISOR);
+    apply!(reward_per_kill, config_fields::REWARD_PER_KILL);
+    apply!(protocol_fee_bps, config_fields::PROTOCOL_FEE_BPS);
+    apply!(update_delay_seconds, config_fields::UPDATE_DELAY_SECONDS);
+
+    // Same post-apply checks as update_config_handler
+    require!(config.min_bet_amount > 0, WagerError::InvalidConfig);
+    require!(config.max_bet_amount >= config.min_bet_amount, WagerError::InvalidConfig);
+    require!(config.spawn_cost_divisor > 0, WagerError::InvalidConfig);
+    require!(config.protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS, WagerError::InvalidConfig);
+    require!(config.update_delay_seconds >= MIN_UPDATE_DELAY_SECONDS, WagerError::InvalidConfig);
+
+    emit!(ConfigUpdated { admin: config.admin, changed_fields: changed });
+    Ok(())
+}
+```
+// Cases to cover: apply one second before update_effective_at fails with
+// TimelockNotElapsed; apply at/after it succeeds and clears pending_update;
+// a second queue replaces the first and pushes update_effective_at out again,
+// so only the second update's fields land. initialize_config with a delay
+// under a day -> InvalidConfig; a queued delay change takes effect only after
+// the old delay has elapsed, and a queued delay under a day fails at apply.
@@ -21,7 +21,52 @@ pub fn get_player_spawns(&self, player_pubkey: Pubkey) -> Result<u16> {
 
 // For distribution, use only meaningful metrics:
 let player_kills = game_session.get_player_kills(player)?;
-let earnings = player_kills as u64 * reward_per_kill;
+let earnings = (player_kills as u64)
+    .checked_mul(reward_per_kill)
+    .ok_or(WagerError::ArithmeticOverflow)?;
+
+// Implemented on top of TeamSide / Option slots. get_kills_and_spawns is
+// deleted - its summed value was only ever used to overpay spawn hoarders.
+impl GameSession {
+    pub fn get_player_kills(&self, player: Pubkey) -> Result<u16> {
+        let (side, i) = self.locate(player)?;
+        Ok(self.team(side).player_kills[i])
+    }
+
+    pub fn get_player_spawns(&self, player: Pubkey) -> Result<u16> {
+        let (side, i) = self.locate(player)?;
+        Ok(self.team(side).player_spawns[i])
+    }
+}
+
+pub fn distribute_pay_spawn_earnings(ctx: Context<DistributeWinnings>, _session_id: SessionId) -> Result<()> {
+    let game_session = &ctx.accounts.game_session;
+    require!(game_session.game_mode.is_pay_to_spawn(), WagerError::WrongDistributionForMode);
+    let reward_per_kill = ctx.accounts.game_config.reward_per_kill;
+
+    // Work out every payout first; nothing moves unless the vault covers all of it
+    let mut payouts = Vec::with_capacity(10);
+    let mut total: u64 = 0;
+    for player in game_session.get_all_players() {
+        let kills = game_session.get_player_kills(player)? as u64;
+        let earnings = kills.checked_mul(reward_per_kill).ok_or(WagerError::ArithmeticOverflow)?;
+        total = math::add_amount(total, earnings)?;
+        payouts.push((player, earnings));
+    }
+    require!(
+        total <= ctx.accounts.vault_token_account.amount,
+        WagerError::InsufficientVaultBalance
+    );
+
+    for (player, earnings) in payouts {
+        if earnings > 0 {
+            anchor_spl::token::transfer(/* vault -> player's derived ATA */, earnings)?;
+        }
+    }
+    // ... leftover (unused spawn purchases) to the treasury, vault-empty check (FC-005)
+    Ok(())
+}
+// Case: 0 kills and 15 unused spawns -> earnings 0, no transfer to that player.
 
 
 // ## FM-002: Fixed Array Size Inefficiency
@@ -42,28 +87,269 @@ pub struct Team {
 // Cost: +5 bytes per team, but clearer semantics
 
 // Option 3: Use Enum for fixed team sizes
-#[derive(AnchorSerialize, AnchorDeserialize)]
+// One InitSpace-derived roster per size, so every size below comes from the
+// derive rather than a hand count. Empty slots are None (33 bytes a key), not
+// a Pubkey::default() sentinel. Carries every per-slot array the inline Team
+// has across the series (deposits ENH-023, bonus spawns ENH-040, holds
+// ENH-033, deaths/assists ENH-081, streaks ENH-084), so switching layouts
+// loses nothing.
+macro_rules! roster {
+    ($name:ident, $n:expr) => {
+        #[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+        pub struct $name {
+            pub players: [Option<Pubkey>; $n],
+            pub spawns: [u16; $n],
+            pub kills: [u16; $n],
+            pub player_total_paid: [u64; $n],
+            pub player_deaths: [u16; $n],
+            pub player_assists: [u16; $n],
+            pub current_streak: [u8; $n],
+            pub best_streak: [u8; $n],
+            pub bonus_spawns_granted: [u16; $n],
+            pub slot_state: [SlotState; $n],   // SlotState derives InitSpace (9 bytes, Held)
+            pub total_bet: u64,
+        }
+    };
+}
+roster!(RosterOne, 1);
+roster!(RosterTwo, 2);
+roster!(RosterThree, 3);
+roster!(RosterFour, 4);
+roster!(RosterFive, 5);
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub enum Team {
-    OneVsOne {
-        players: [Pubkey; 1],
-        spawns: [u16; 1], 
-        kills: [u16; 1],
-        total_bet: u64,
-    },
-    ThreeVsThree {
-        players: [Pubkey; 3],
-        spawns: [u16; 3],
-        kills: [u16; 3], 
-        total_bet: u64,
-    },
-    FiveVsFive {
-        players: [Pubkey; 5],
-        spawns: [u16; 5],
-        kills: [u16; 5],
-        total_bet: u64,
-    },
+    NoTeams,   // FreeForAll (ENH-062): the pool lives in session.ffa
+    OneVsOne(RosterOne),
+    TwoVsTwo(RosterTwo),
+    ThreeVsThree(RosterThree),
+    FourVsFour(RosterFour),
+    FiveVsFive(RosterFive),
 }// this has zero space overhead but more complex code
 
+// Keeping option 3 manageable: only this macro matches on variants, and
+// NoTeams answers every per-slot accessor with an empty slice
+macro_rules! per_slot {
+    ($team:expr, $r:ident => $body:expr, $empty:expr) => {
+        match $team {
+            Team::NoTeams => $empty,
+            Team::OneVsOne($r) => $body,
+            Team::TwoVsTwo($r) => $body,
+            Team::ThreeVsThree($r) => $body,
+            Team::FourVsFour($r) => $body,
+            Team::FiveVsFive($r) => $body,
+        }
+    };
+}
+
+impl Team {
+    pub fn for_mode(game_mode: GameMode) -> Result<Self> {
+        Ok(match game_mode.players_per_team() {
+            0 => Team::NoTeams,   // FreeForAll
+            1 => Team::OneVsOne(RosterOne::default()),
+            2 => Team::TwoVsTwo(RosterTwo::default()),
+            3 => Team::ThreeVsThree(RosterThree::default()),
+            4 => Team::FourVsFour(RosterFour::default()),
+            5 => Team::FiveVsFive(RosterFive::default()),
+            _ => return Err(error!(WagerError::InvalidTeamSize)),
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.players().len()
+    }
+
+    pub fn players(&self) -> &[Option<Pubkey>] {
+        per_slot!(self, r => &r.players, &[])
+    }
+
+    pub fn players_mut(&mut self) -> &mut [Option<Pubkey>] {
+        per_slot!(self, r => &mut r.players, &mut [])
+    }
+
+    pub fn total_bet_mut(&mut self) -> Result<&mut u64> {
+        per_slot!(self, r => Ok(&mut r.total_bet), Err(error!(WagerError::InvalidTeamSize)))
+    }
+
+    pub fn kills_mut(&mut self, i: usize) -> Result<&mut u16> {
+        slot(per_slot!(self, r => &mut r.kills, &mut []), i)
+    }
+
+    pub fn spawns_mut(&mut self, i: usize) -> Result<&mut u16> {
+        slot(per_slot!(self, r => &mut r.spawns, &mut []), i)
+    }
+
+    pub fn total_paid_mut(&mut self, i: usize) -> Result<&mut u64> {
+        slot(per_slot!(self, r => &mut r.player_total_paid, &mut []), i)
+    }
+
+    pub fn deaths_mut(&mut self, i: usize) -> Result<&mut u16> {
+        slot(per_slot!(self, r => &mut r.player_deaths, &mut []), i)
+    }
+
+    pub fn assists_mut(&mut self, i: usize) -> Result<&mut u16> {
+        slot(per_slot!(self, r => &mut r.player_assists, &mut []), i)
+    }
+
+    pub fn current_streak_mut(&mut self, i: usize) -> Result<&mut u8> {
+        slot(per_slot!(self, r => &mut r.current_streak, &mut []), i)
+    }
+
+    pub fn best_streak_mut(&mut self, i: usize) -> Result<&mut u8> {
+        slot(per_slot!(self, r => &mut r.best_streak, &mut []), i)
+    }
+
+    pub fn bonus_spawns_mut(&mut self, i: usize) -> Result<&mut u16> {
+        slot(per_slot!(self, r => &mut r.bonus_spawns_granted, &mut []), i)
+    }
+
+    pub fn slot_state_mut(&mut self, i: usize) -> Result<&mut SlotState> {
+        slot(per_slot!(self, r => &mut r.slot_state, &mut []), i)
+    }
+
+    /// Serialized size of this mode's variant: enum tag + that roster
+    pub fn space(game_mode: GameMode) -> Result<usize> {
+        let roster = match game_mode.players_per_team() {
+            0 => 0,
+            1 => RosterOne::INIT_SPACE,
+            2 => RosterTwo::INIT_SPACE,
+            3 => RosterThree::INIT_SPACE,
+            4 => RosterFour::INIT_SPACE,
+            5 => RosterFive::INIT_SPACE,
+            _ => return Err(error!(WagerError::InvalidTeamSize)),
+        };
+        Ok(1 + roster)
+    }
+}
+
+fn slot<T>(slots: &mut [T], i: usize) -> Result<&mut T> {
+    slots.get_mut(i).ok_or(error!(WagerError::InvalidPlayerIndex))
+}
+
+// Size the account from the mode instead of INIT_SPACE's 5v5 worst case.
+// Still 8 + GameSession::INIT_SPACE like every other init (FC-001), minus the
+// two teams' FiveVsFive bytes this mode never uses.
+#[derive(Accounts)]
+#[instruction(session_id: SessionId, bet_amount: u64, game_mode: GameMode)]
+pub struct CreateGameSession<'info> {
+    #[account(
+        init,
+        payer = game_server,
+        space = 8 + GameSession::INIT_SPACE - 2 * (Team::INIT_SPACE - Team::space(game_mode)?),
+        seeds = [b"game_session", game_server.key().as_ref(), session_id.as_ref()],
+        bump
+    )]
+    pub game_session: Account<'info, GameSession>,
+    // ... existing accounts
+}
+
+// create_game_session_handler:
+//     game_session.team_a = Team::for_mode(game_mode)?;
+//     game_session.team_b = Team::for_mode(game_mode)?;
+// A slot is 33 (key) + 2 + 2 (spawns, kills) + 8 (paid) + 2 + 2 (deaths,
+// assists) + 1 + 1 (streaks) + 2 (bonus spawns) + 9 (slot_state) = 62 bytes,
+// and a roster is n * 62 + 8 (total_bet).
+// Cases (Team::space): 1v1 -> 71, 2v2 -> 133, 3v3 -> 195, 4v4 -> 257,
+// 5v5 -> 319 = Team::INIT_SPACE, FreeForAll -> 1 (NoTeams tag only).
+// Cases (account size): 5v5 -> 8 + GameSession::INIT_SPACE exactly; 1v1 is
+// 2 * 248 = 496 bytes smaller; FreeForAll is 2 * 318 = 636 bytes smaller and
+// creates, with capacity() 0 on both teams.
+// Cases (accessors): kills_mut(1) on a 1v1 roster and any per-slot accessor on
+// NoTeams -> InvalidPlayerIndex; total_bet_mut on NoTeams -> InvalidTeamSize;
+// a 3v3 round trip (serialize, deserialize) keeps every per-slot array.
+
+// 2v2 and 4v4 lobbies: new modes only, every size-dependent path already goes
+// through players_per_team()
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum GameMode {
+    WinnerTakesAllOneVsOne,
+    WinnerTakesAllThreeVsThree,
+    WinnerTakesAllFiveVsFive,
+    PayToSpawnOneVsOne,
+    PayToSpawnThreeVsThree,
+    PayToSpawnFiveVsFive,
+    AllPayFiveVsFive,          // ENH-029
+    EliminationThreeVsThree,   // ENH-041
+    EliminationFiveVsFive,
+    // after every variant above, so no stored index shifts
+    WinnerTakesAllTwoVsTwo,
+    WinnerTakesAllFourVsFour,
+    PayToSpawnTwoVsTwo,
+    PayToSpawnFourVsFour,
+    // FreeForAll (ENH-062) goes after these
+}
+
+impl GameMode {
+    // No `_ =>` arm, so a new mode has to state its team size
+    pub fn players_per_team(&self) -> usize {
+        match self {
+            Self::WinnerTakesAllOneVsOne | Self::PayToSpawnOneVsOne => 1,
+            Self::WinnerTakesAllTwoVsTwo | Self::PayToSpawnTwoVsTwo => 2,
+            Self::WinnerTakesAllThreeVsThree
+            | Self::PayToSpawnThreeVsThree
+            | Self::EliminationThreeVsThree => 3,
+            Self::WinnerTakesAllFourVsFour | Self::PayToSpawnFourVsFour => 4,
+            Self::WinnerTakesAllFiveVsFive
+            | Self::PayToSpawnFiveVsFive
+            | Self::AllPayFiveVsFive
+            | Self::EliminationFiveVsFive => 5,
+            // no teams: every team path sees zero slots, the pool uses ffa.capacity
+            Self::FreeForAll => 0,
+        }
+    }
+}
+// Still one byte (fieldless enum), so INIT_SPACE doesn't change.
+// get_empty_slot(players_per_team) already refuses index >= 2 in a 2v2, and
+// distribute splits the pot over the winning team's occupied slots, so a full
+// 2v2 pays each winner half the pot. End to end: create 2v2 at bet 100, four
+// joins (vault 400), record kills, distribute -> two winners get 200 each,
+// vault 0; a fifth join (third on either team) fails with TeamIsFull. Stored
+// AllPay / Elimination sessions still decode to the same modes.
+
+// Each distribution path only runs for its own modes
+impl GameMode {
+    pub fn is_pay_to_spawn(&self) -> bool {
+        matches!(
+            self,
+            Self::PayToSpawnOneVsOne
+                | Self::PayToSpawnTwoVsTwo
+                | Self::PayToSpawnThreeVsThree
+                | Self::PayToSpawnFourVsFour
+                | Self::PayToSpawnFiveVsFive
+        )
+    }
+
+    /// Winning team splits the pot. Elimination pays the same way; it only
+    /// differs in how the game ends. AllPay and FreeForAll have their own paths.
+    pub fn is_winner_takes_all(&self) -> bool {
+        matches!(
+            self,
+            Self::WinnerTakesAllOneVsOne
+                | Self::WinnerTakesAllTwoVsTwo
+                | Self::WinnerTakesAllThreeVsThree
+                | Self::WinnerTakesAllFourVsFour
+                | Self::WinnerTakesAllFiveVsFive
+                | Self::EliminationThreeVsThree
+                | Self::EliminationFiveVsFive
+        )
+    }
+}
+
+// distribute_all_winnings_handler: a positive check, so a newly added mode
+// is refused until it's listed rather than let in by "not pay-to-spawn"
+require!(game_session.game_mode.is_winner_takes_all(), WagerError::WrongDistributionForMode);
+
+// distribute_pay_spawn_earnings
+require!(game_session.game_mode.is_pay_to_spawn(), WagerError::WrongDistributionForMode);
+
+// pay_to_spawn_handler: no spawn sales in winner-takes-all lobbies
+require!(game_session.game_mode.is_pay_to_spawn(), WagerError::WrongDistributionForMode);
+
+// Cases: distribute_pay_spawn_earnings on a WinnerTakesAllThreeVsThree session,
+// distribute_all_winnings on a PayToSpawnOneVsOne session and pay_to_spawn in a
+// winner-takes-all session all fail with WrongDistributionForMode, and so does
+// distribute_all_winnings on an AllPayFiveVsFive or FreeForAll session.
+
 
 
 // ## FM-003: Economic Imbalance in Spawn Pricing
@@ -75,11 +361,13 @@ pub struct GameConfig {
     pub entry_fee: u64,           // Cost to join game
     pub spawn_cost: u64,          // Cost per 10 spawns
     pub spawn_multiplier: u16,    // How many spawns per purchase
+    // ...existing fields (max_spawns_per_player)
 }
 
-pub fn pay_to_spawn_handler(...) -> Result<()> {
+pub fn pay_to_spawn_handler(ctx: Context<PayToSpawn>, _session_id: SessionId, team_side: TeamSide) -> Result<()> {
     let game_session = &mut ctx.accounts.game_session;
     let config = &ctx.accounts.game_config;
+    let player_index = game_session.get_player_index(team_side, ctx.accounts.user.key())?;
     
     // Use spawn-specific pricing
     let spawn_cost = config.spawn_cost;  // E.g., 25% of entry fee
@@ -87,12 +375,68 @@ pub fn pay_to_spawn_handler(...) -> Result<()> {
     anchor_spl::token::transfer(/* ... */, spawn_cost)?;
     
     // Add configurable number of spawns
-    let spawns_to_add = config.spawn_multiplier;
-    game_session.add_spawns_amount(team, player_index, spawns_to_add)?;
+    game_session.add_spawns_amount(team_side, player_index, config.spawn_multiplier, config.max_spawns_per_player)?;
     
     Ok(())
 }
 
+// Or price spawns off the bet with the FIN-002 config (spawn_cost_divisor)
+impl GameConfig {
+    pub fn spawn_cost(&self, session_bet: u64) -> Result<u64> {
+        require!(self.spawn_cost_divisor > 0, WagerError::InvalidConfig);
+        let cost = session_bet / self.spawn_cost_divisor as u64;
+        require!(cost > 0, WagerError::SpawnCostTooLow);   // tiny bet / large divisor rounds to 0
+        Ok(cost)
+    }
+}
+
+pub fn pay_to_spawn_handler(ctx: Context<PayToSpawn>, _session_id: SessionId, team_side: TeamSide) -> Result<()> {
+    let config = &ctx.accounts.game_config;
+    let game_session = &mut ctx.accounts.game_session;
+    // no spawn sales in winner-takes-all lobbies (see is_pay_to_spawn below)
+    require!(game_session.game_mode.is_pay_to_spawn(), WagerError::WrongDistributionForMode);
+
+    let spawn_cost = config.spawn_cost(game_session.session_bet)?;
+    let player_index = game_session.get_player_index(team_side, ctx.accounts.user.key())?;
+
+    // cap first (FH-002), so a rejected purchase never moves tokens
+    let purchase = config.spawn_purchase_count as u16;
+    let max = config.max_spawns_per_player as u16;
+    let current = game_session.team(team_side).player_spawns[player_index];
+    require!(current + purchase <= max, WagerError::TooManySpawns);
+
+    anchor_spl::token::transfer(/* user -> vault */, spawn_cost)?;
+
+    // book exactly what moved: VaultState, MintStats and the TVL cap (ENH-034)
+    let mut vault = VaultAccounts {
+        token_program: &ctx.accounts.token_program,
+        mint: &ctx.accounts.mint,
+        vault_state: &mut ctx.accounts.vault_state,
+        vault_token_account: &ctx.accounts.vault_token_account,
+        mint_stats: &mut ctx.accounts.mint_stats,
+        game_config: config,
+        protocol_stats: &mut ctx.accounts.protocol_stats,
+        game_session: game_session.key(),
+        vault_bump: game_session.vault_bump,
+    };
+    book_deposit(&mut vault, spawn_cost, false)?;
+
+    // team pot and the slot's own total, which refunds pay back (ENH-023)
+    let team = game_session.team_mut(team_side);
+    team.total_bet = math::add_amount(team.total_bet, spawn_cost)?;
+    team.player_total_paid[player_index] = math::add_amount(team.player_total_paid[player_index], spawn_cost)?;
+
+    game_session.add_spawns_amount(team_side, player_index, purchase, config.max_spawns_per_player)?;
+    Ok(())
+}
+// Case: bet 1000, divisor 4 -> vault grows by 250 per purchase, not 1000, and
+// the buyer's player_total_paid goes 1000 -> 1250 -> 1500; a refund returns 1500.
+// Cases: a purchase past max_spawns_per_player -> TooManySpawns with the vault
+// unchanged; any purchase in a winner-takes-all session ->
+// WrongDistributionForMode.
+// Pay-to-spawn payouts are kills * reward_per_kill, so they stay solvent as
+// long as the sum is checked against vault_state.current_balance before paying.
+
 
 // ## FM-004: No Bet Amount Validation
 // SECURE: Add bet amount validation
@@ -107,6 +451,11 @@ pub struct GameConfig {
 pub fn create_game_session_handler(...) -> Result<()> {
     let config = &ctx.accounts.game_config;
     
+    // Zero is never valid, even if min_bet_amount is misconfigured to 0:
+    // spawn cost is bet / divisor and a zero pot breaks distribution.
+    // (Practice sessions, ENH-027, skip all three bet checks.)
+    require!(bet_amount > 0, WagerError::BetTooLow);
+
     // Validate bet amount is within acceptable range
     require!(
         bet_amount >= config.min_bet_amount,
@@ -123,6 +472,8 @@ pub fn create_game_session_handler(...) -> Result<()> {
     
     // ... rest of function
 }
+// game_config comes from CreateGameSession (FM-013). Boundaries: min and max
+// accepted, min - 1 -> BetTooLow, max + 1 -> BetTooHigh, 0 -> BetTooLow.
 
 
 // ## FM-005: Integer Overflow in Kill/Spawn Counters
@@ -139,6 +490,45 @@ pub fn add_kill(/* ... */) -> Result<()> {
     Ok(())
 }
 
+// Same rule across the whole state module:
+//   gameplay counters (kills) -> saturating, a capped counter is harmless
+//   money (total_bet, total_refunded, earnings, vault balances) -> checked, overflow
+//   is an error (WagerError::ArithmeticOverflow), never a wrap or a silent clamp
+pub mod math {
+    use super::*;
+
+    pub fn add_amount(a: u64, b: u64) -> Result<u64> {
+        a.checked_add(b).ok_or(error!(WagerError::ArithmeticOverflow))
+    }
+
+    pub fn sub_amount(a: u64, b: u64) -> Result<u64> {
+        a.checked_sub(b).ok_or(error!(WagerError::ArithmeticOverflow))
+    }
+
+    /// a * num / den through u128, so the product can't overflow and the
+    /// result is only truncated once
+    pub fn mul_div(a: u64, num: u64, den: u64) -> Result<u64> {
+        require!(den > 0, WagerError::ArithmeticOverflow);
+        let result = (a as u128)
+            .checked_mul(num as u128)
+            .ok_or(WagerError::ArithmeticOverflow)?
+            / den as u128;
+        u64::try_from(result).map_err(|_| error!(WagerError::ArithmeticOverflow))
+    }
+}
+
+// join_user_handler / pay_to_spawn_handler
+team.total_bet = math::add_amount(team.total_bet, amount)?;          // was +=
+// refund_wager_handler
+refund_state.total_refunded = math::add_amount(refund_state.total_refunded, refund)?;
+// distribute_pay_spawn_earnings: kills * session_bet / 10
+let earnings = math::mul_div(game_session.session_bet, player_kills as u64, 10)?;
+// add_kill: kills saturate (above); spawns go through add_spawns_amount (FH-001)
+
+// Boundaries: add_amount(u64::MAX, 1) -> ArithmeticOverflow; mul_div(u64::MAX,
+// 10, 10) == u64::MAX (u128 intermediate, no overflow); mul_div(u64::MAX, 11, 10)
+// -> ArithmeticOverflow on the way back to u64; kills at u16::MAX stay there.
+
 
 // ## FM-006: Session State Enum Incomplete
 // COMPLETE: Add all necessary states
@@ -161,4 +551,236 @@ impl Default for GameStatus {
 }
 
 // Use appropriate states:
-pub fn refund_wager_handler(/* ... */) -> Result
\ No newline at end of file
+pub fn refund_wager_handler(/* ... */) -> Result<()> {
+    // ...
+    // Refunded, not Completed
+    game_session.transition(
+        session_key,
+        GameStatus::Refunded,
+        StatusReason::AuthorityAction,
+        ctx.accounts.game_server.key(),
+        &mut ctx.accounts.mint_stats,
+    )?;
+    Ok(())
+}
+
+// BETTER: make the state machine explicit so no handler can, say, complete a
+// cancelled game. Draw is the ENH-063 variant.
+impl GameStatus {
+    pub fn can_transition_to(&self, next: GameStatus) -> bool {
+        use GameStatus::*;
+        matches!(
+            (self, next),
+            (WaitingForPlayers, InProgress)
+                | (WaitingForPlayers, Cancelled)
+                | (WaitingForPlayers, Refunded)
+                | (WaitingForPlayers, Abandoned)   // lobby never filled
+                | (InProgress, Completed)
+                | (InProgress, Refunded)           // FFA with no kills, emergency refund
+                | (InProgress, PendingSettlement)  // finalize opens the dispute window (ENH-089)
+                | (InProgress, Abandoned)
+                | (InProgress, Draw)
+                | (PendingSettlement, Completed)   // window passed
+                | (PendingSettlement, Disputed)    // a dispute needs a recorded result
+                | (Cancelled, Refunded)            // refunds after a cancel / expired lobby
+                | (Disputed, Completed)            // resolved to a winner
+                | (Disputed, Refunded)             // resolved to a refund
+                | (Draw, Refunded)
+                | (Abandoned, Refunded)
+        )
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, GameStatus::Completed | GameStatus::Refunded | GameStatus::Cancelled)
+    }
+}
+
+// Handlers never assign status directly; they go through
+// GameSession::transition (ENH-018), which checks can_transition_to and returns
+// WagerError::InvalidStateTransition otherwise:
+//     game_session.status = GameStatus::Refunded;
+// becomes
+//     game_session.transition(session_key, GameStatus::Refunded, StatusReason::AuthorityAction, actor, &mut ctx.accounts.mint_stats)?;
+
+// Matrix (rows = from, x = allowed), every other pair must fail, including
+// X -> X and anything out of a terminal state except Cancelled -> Refunded
+// (MintStats still counts the session closed once, on the Cancelled edge):
+//                      Wait  InProg  Compl  Cancel  Refund  Disput  Aband  Draw  Pending
+//   WaitingForPlayers        x              x       x               x
+//   InProgress                       x              x               x      x      x
+//   Completed
+//   Cancelled                                       x
+//   Refunded
+//   Disputed                         x              x
+//   Abandoned                                       x
+//   Draw                                            x
+//   PendingSettlement                x                      x
+
+
+// ## FM-013: Missing Config Account
+// SECURE: Create the GameConfig singleton once, with validated parameters
+// (full struct documented in FIN-002)
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,   // PDA already exists => init fails, so re-initialization is impossible
+        payer = admin,
+        space = 8 + GameConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1000;  // 10%
+
+pub fn initialize_config_handler(
+    ctx: Context<InitializeConfig>,
+    min_bet_amount: u64,
+    max_bet_amount: u64,
+    spawn_cost_divisor: u8,
+    initial_spawn_count: u8,
+    max_spawns_per_player: u8,
+    protocol_fee_bps: u16,
+    max_game_duration: i64,
+) -> Result<()> {
+    // Sanity validation
+    require!(min_bet_amount > 0, WagerError::InvalidConfig);
+    require!(min_bet_amount <= max_bet_amount, WagerError::InvalidConfig);
+    require!(spawn_cost_divisor > 0, WagerError::InvalidConfig);
+    require!(initial_spawn_count <= max_spawns_per_player, WagerError::InvalidConfig);
+    require!(protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS, WagerError::InvalidConfig);
+    require!(max_game_duration > 0, WagerError::InvalidConfig);
+
+    let config = &mut ctx.accounts.game_config;
+    config.admin = ctx.accounts.admin.key();
+    config.min_bet_amount = min_bet_amount;
+    config.max_bet_amount = max_bet_amount;
+    config.spawn_cost_divisor = spawn_cost_divisor;
+    config.initial_spawn_count = initial_spawn_count;
+    config.max_spawns_per_player = max_spawns_per_player;
+    config.protocol_fee_bps = protocol_fee_bps;
+    config.max_game_duration = max_game_duration;
+    config.authorized_servers = Vec::new();
+    Ok(())
+}
+
+// Wire the config into session creation so FM-004 bet validation can use it
+#[derive(Accounts)]
+#[instruction(session_id: SessionId)]
+pub struct CreateGameSession<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub game_config: Account<'info, GameConfig>,
+    // ... existing accounts
+}
+
+// Authorized server list: bounded, admin-managed, checked on create
+pub const MAX_AUTHORIZED_SERVERS: usize = 16;
+
+// GameConfig (FIN-002):
+//     #[max_len(MAX_AUTHORIZED_SERVERS)]
+//     pub authorized_servers: Vec<Pubkey>,
+// INIT_SPACE already reserves all 16 slots, so add/remove never needs realloc.
+
+pub fn add_authorized_server_handler(ctx: Context<AdminOnly>, server: Pubkey) -> Result<()> {
+    let servers = &mut ctx.accounts.game_config.authorized_servers;
+    require!(!servers.contains(&server), WagerError::ServerAlreadyAuthorized);
+    require!(servers.len() < MAX_AUTHORIZED_SERVERS, WagerError::TooManyServers);
+    servers.push(server);
+    Ok(())
+}
+
+pub fn remove_authorized_server_handler(ctx: Context<AdminOnly>, server: Pubkey) -> Result<()> {
+    let servers = &mut ctx.accounts.game_config.authorized_servers;
+    let index = servers
+        .iter()
+        .position(|s| *s == server)
+        .ok_or(WagerError::ServerNotAuthorized)?;
+    servers.swap_remove(index);
+    Ok(())
+}
+
+pub fn create_game_session_handler(ctx: Context<CreateGameSession>, /* ... */) -> Result<()> {
+    require!(
+        ctx.accounts.game_config.authorized_servers.contains(&ctx.accounts.game_server.key()),
+        WagerError::UnauthorizedServer
+    );
+    // ... rest of function
+}
+
+// Only creation is gated. Sessions already created keep game_session.authority
+// and distribute/refund check that, so de-listing a server stops new lobbies
+// without stranding funds in its running games. (With server roles below, the
+// per-role checks do apply to running games - revoke CREATE to stop new lobbies.)
+
+// Server roles: one compromised key shouldn't be able to do everything
+pub mod server_roles {
+    pub const CREATE: u8 = 1 << 0;
+    pub const RECORD: u8 = 1 << 1;
+    pub const DISTRIBUTE: u8 = 1 << 2;
+    pub const REFUND: u8 = 1 << 3;
+    pub const ALL: u8 = CREATE | RECORD | DISTRIBUTE | REFUND;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ServerEntry {
+    pub key: Pubkey,
+    pub roles: u8,   // server_roles bits
+}
+
+// GameConfig:
+//     #[max_len(MAX_AUTHORIZED_SERVERS)]
+//     pub authorized_servers: Vec<ServerEntry>,   // was Vec<Pubkey>
+// add_authorized_server_handler(server, roles) pushes a ServerEntry; duplicates
+// are still checked on `key`. remove_authorized_server_handler is unchanged
+// apart from matching on entry.key. (ENH-036 moves both onto their own
+// contexts so they also create / deactivate the AuthorizedServer PDA.)
+
+impl GameConfig {
+    pub fn require_role(&self, server: Pubkey, role: u8) -> Result<()> {
+        let entry = self
+            .authorized_servers
+            .iter()
+            .find(|e| e.key == server)
+            .ok_or(WagerError::UnauthorizedServer)?;
+        require!(entry.roles & role == role, WagerError::MissingServerRole);
+        Ok(())
+    }
+}
+
+pub fn grant_server_roles_handler(ctx: Context<AdminOnly>, server: Pubkey, roles: u8) -> Result<()> {
+    require!(roles & !server_roles::ALL == 0, WagerError::InvalidConfig);
+    let entry = ctx.accounts.game_config
+        .authorized_servers
+        .iter_mut()
+        .find(|e| e.key == server)
+        .ok_or(WagerError::ServerNotAuthorized)?;
+    entry.roles |= roles;
+    Ok(())
+}
+
+pub fn revoke_server_roles_handler(ctx: Context<AdminOnly>, server: Pubkey, roles: u8) -> Result<()> {
+    let entry = ctx.accounts.game_config
+        .authorized_servers
+        .iter_mut()
+        .find(|e| e.key == server)
+        .ok_or(WagerError::ServerNotAuthorized)?;
+    entry.roles &= !roles;
+    Ok(())
+}
+
+// Checked at the top of each handler, on top of the session authority constraint:
+//     create_game_session_handler      config.require_role(game_server, server_roles::CREATE)?
+//     record_kill(s)_handler           config.require_role(game_server, server_roles::RECORD)?
+//     distribute_* / finalize_game     config.require_role(game_server, server_roles::DISTRIBUTE)?
+//     refund_wager / declare_draw      config.require_role(game_server, server_roles::REFUND)?
+// Retiring a server: revoke CREATE first, remove the entry once its sessions
+// have settled (a removed server's stragglers go through claim_abandoned_game).
+// Cases: RECORD-only server creating a game -> MissingServerRole; the same
+// server recording a kill in a session it owns succeeds.
@@ -0,0 +1,605 @@
+// ENH-008 harness: random but type-valid instruction sequences against a
+// solana-program-test bank, with invariants::check_all after every accepted
+// instruction.
+//
+//     cargo test --features fuzz --test fuzz
+//     FUZZ_SEED=1234 cargo test --features fuzz --test fuzz   // replay one run
+//
+// Regressions live in tests/fuzz-regressions.txt and run before the random
+// cases. The random cases walk a fixed seed list so CI is deterministic; a
+// failing run prints its seed - add it there as `seed <u64>`.
+#![cfg(feature = "fuzz")]
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    clock::Clock, instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+use solana_sdk::sysvar::instructions::ID as INSTRUCTIONS_SYSVAR_ID;
+use wager_program::{
+    accounts, instruction, invariants, server_roles, GameConfig, GameMode, GameSession, GameStatus, KillEvent,
+    SessionId, Settlement, TeamSide, VaultState, ID as PROGRAM_ID,
+};
+
+const PLAYERS: usize = 10;
+const MAX_OPS: usize = 64;
+const RANDOM_RUNS: u64 = 256;
+const SESSION_ID: SessionId = *b"fuzz\0\0\0\0\0\0";
+const REGRESSIONS: &str = include_str!("fuzz-regressions.txt");
+
+#[derive(Debug, Clone)]
+enum Op {
+    Create { mode: u8, bet: u64 },
+    Join { player: u8, side: TeamSide },
+    Kill { killer: u8, victim: u8 },
+    BuySpawns { player: u8 },
+    Leave { player: u8 },
+    Start,
+    Distribute { winner: TeamSide },
+    Refund,
+    ClaimRefund { player: u8 },
+    Crank { advance_secs: i64 },
+}
+
+fn side() -> impl Strategy<Value = TeamSide> {
+    prop_oneof![Just(TeamSide::A), Just(TeamSide::B)]
+}
+
+fn player() -> impl Strategy<Value = u8> {
+    0..PLAYERS as u8
+}
+
+fn create() -> impl Strategy<Value = Op> {
+    (0u8..6, 1u64..1_000_000).prop_map(|(mode, bet)| Op::Create { mode, bet })
+}
+
+fn join() -> impl Strategy<Value = Op> {
+    (player(), side()).prop_map(|(player, side)| Op::Join { player, side })
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        create(),
+        join(),
+        (player(), player()).prop_map(|(killer, victim)| Op::Kill { killer, victim }),
+        player().prop_map(|player| Op::BuySpawns { player }),
+        player().prop_map(|player| Op::Leave { player }),
+        Just(Op::Start),
+        side().prop_map(|winner| Op::Distribute { winner }),
+        Just(Op::Refund),
+        player().prop_map(|player| Op::ClaimRefund { player }),
+        (0i64..7 * 86_400).prop_map(|advance_secs| Op::Crank { advance_secs }),
+    ]
+}
+
+/// Always opens with a valid create and join, so every run reaches a live
+/// session instead of bouncing off "no session" for 64 ops
+fn sequence() -> impl Strategy<Value = Vec<Op>> {
+    (create(), join(), prop::collection::vec(op(), 0..MAX_OPS - 2)).prop_map(|(create, join, rest)| {
+        let mut ops = vec![create, join];
+        ops.extend(rest);
+        ops
+    })
+}
+
+struct Harness {
+    ctx: ProgramTestContext,
+    server: Keypair,
+    players: Vec<Keypair>,
+    mint: Pubkey,
+    prev_status: Option<GameStatus>,
+}
+
+impl Harness {
+    async fn new() -> Self {
+        let program = ProgramTest::new("wager_program", PROGRAM_ID, processor!(wager_program::entry));
+        let mut ctx = program.start_with_context().await;
+        let server = Keypair::new();
+        let players: Vec<Keypair> = (0..PLAYERS).map(|_| Keypair::new()).collect();
+        let mint = setup::bootstrap(&mut ctx, &server, &players).await;
+        Self { ctx, server, players, mint, prev_status: None }
+    }
+
+    fn session(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"game_session", self.server.pubkey().as_ref(), &SESSION_ID], &PROGRAM_ID).0
+    }
+
+    fn pda(&self, seed: &[u8]) -> Pubkey {
+        Pubkey::find_program_address(&[seed, self.session().as_ref()], &PROGRAM_ID).0
+    }
+
+    /// Ok(true) if the program accepted the instruction. Program errors are
+    /// expected (random ops are mostly invalid) and reported as Ok(false).
+    async fn apply(&mut self, op: &Op) -> Result<bool, String> {
+        if let Op::Crank { advance_secs } = op {
+            let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.map_err(|e| e.to_string())?;
+            clock.unix_timestamp += advance_secs;
+            self.ctx.set_sysvar(&clock);
+            return Ok(false);   // nothing to check, no state moved
+        }
+        // record_kill takes the sequence the server last read (ENH-081)
+        let kill_sequence = match op {
+            Op::Kill { .. } => self.load::<GameSession>(self.session()).await.map_or(0, |s| s.kill_sequence),
+            _ => 0,
+        };
+        let blockhash = self.ctx.get_new_latest_blockhash().await.map_err(|e| e.to_string())?;
+        let (ix, signer) = self.instruction(op, kill_sequence);
+        let signers: Vec<&Keypair> = vec![&self.ctx.payer, signer];
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&self.ctx.payer.pubkey()), &signers, blockhash);
+        Ok(self.ctx.banks_client.process_transaction(tx).await.is_ok())
+    }
+
+    fn instruction(&self, op: &Op, kill_sequence: u64) -> (Instruction, &Keypair) {
+        let session = self.session();
+        let config = setup::global(b"config");
+        let vault_state = self.pda(b"vault");
+        let vault_token_account = self.pda(b"vault_token");
+        let mint_stats = Pubkey::find_program_address(&[b"mint_stats", self.mint.as_ref()], &PROGRAM_ID).0;
+        let protocol_stats = setup::global(b"protocol_stats");
+        let registry = setup::global(b"registry");
+        let treasury_token_account = setup::ata(&setup::global(b"treasury"), &self.mint);
+        let ata = |who: &Keypair| setup::ata(&who.pubkey(), &self.mint);
+        let per_player = |seed: &[u8], who: &Keypair| {
+            Pubkey::find_program_address(&[seed, who.pubkey().as_ref()], &PROGRAM_ID).0
+        };
+
+        let (data, metas, signer) = match *op {
+            Op::Create { mode, bet } => (
+                instruction::CreateGameSession { session_id: SESSION_ID, bet_amount: bet, game_mode: game_mode(mode) }
+                    .data(),
+                accounts::CreateGameSession {
+                    game_session: session,
+                    game_config: config,
+                    vault_state,
+                    vault_token_account,
+                    mint: self.mint,
+                    mint_stats,
+                    registry,
+                    game_server: self.server.pubkey(),
+                    token_program: spl_token::ID,
+                    system_program: solana_sdk::system_program::ID,
+                }
+                .to_account_metas(None),
+                &self.server,
+            ),
+            Op::Join { player, side } => {
+                let user = &self.players[player as usize];
+                (
+                    instruction::JoinUser { session_id: SESSION_ID, team_side: side }.data(),
+                    accounts::JoinUser {
+                        game_session: session,
+                        game_config: config,
+                        user: user.pubkey(),
+                        user_token_account: ata(user),
+                        vault_state,
+                        vault_token_account,
+                        mint: self.mint,
+                        mint_stats,
+                        protocol_stats,
+                        registry,
+                        ban_record: per_player(b"ban", user),
+                        player_exposure: per_player(b"exposure", user),
+                        player_stats: per_player(b"stats", user),
+                        session_lock: per_player(b"session_lock", user),
+                        invite: None,
+                        authority: self.server.pubkey(),
+                        attestation: None,
+                        instructions: INSTRUCTIONS_SYSVAR_ID,
+                        token_program: spl_token::ID,
+                        system_program: solana_sdk::system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    user,
+                )
+            }
+            Op::BuySpawns { player } => {
+                let user = &self.players[player as usize];
+                (
+                    instruction::PayToSpawn { session_id: SESSION_ID, team_side: self.side_of(player) }.data(),
+                    accounts::PayToSpawn {
+                        game_session: session,
+                        game_config: config,
+                        user: user.pubkey(),
+                        user_token_account: ata(user),
+                        vault_state,
+                        vault_token_account,
+                        mint: self.mint,
+                        mint_stats,
+                        protocol_stats,
+                        instructions: INSTRUCTIONS_SYSVAR_ID,
+                        token_program: spl_token::ID,
+                    }
+                    .to_account_metas(None),
+                    user,
+                )
+            }
+            Op::Kill { killer, victim } => (
+                instruction::RecordKill {
+                    session_id: SESSION_ID,
+                    kill: KillEvent {
+                        killer_team: self.side_of(killer),
+                        killer: self.players[killer as usize].pubkey(),
+                        victim_team: self.side_of(victim),
+                        victim: self.players[victim as usize].pubkey(),
+                        assister: None,
+                    },
+                    expected_sequence: kill_sequence,
+                }
+                .data(),
+                accounts::RecordKill { game_session: session, game_config: config, game_server: self.server.pubkey() }
+                    .to_account_metas(None),
+                &self.server,
+            ),
+            Op::Leave { player } => {
+                let user = &self.players[player as usize];
+                (
+                    instruction::LeaveGame { session_id: SESSION_ID }.data(),
+                    accounts::LeaveGame {
+                        game_session: session,
+                        user: user.pubkey(),
+                        user_token_account: ata(user),
+                        vault_state,
+                        vault_token_account,
+                        mint_stats,
+                        game_config: config,
+                        protocol_stats,
+                        token_program: spl_token::ID,
+                    }
+                    .to_account_metas(None),
+                    user,
+                )
+            }
+            Op::Start => (
+                instruction::StartGame { session_id: SESSION_ID }.data(),
+                accounts::StartGame {
+                    game_session: session,
+                    game_config: config,
+                    mint_stats,
+                    refund_state: self.pda(b"refund"),
+                    game_server: self.server.pubkey(),
+                    system_program: solana_sdk::system_program::ID,
+                }
+                .to_account_metas(None),
+                &self.server,
+            ),
+            Op::Distribute { winner } => (
+                instruction::FinalizeGame { session_id: SESSION_ID, winning_team: winner }.data(),
+                accounts::FinalizeGame {
+                    game_session: session,
+                    settlement: self.pda(b"settlement"),
+                    game_config: config,
+                    mint_stats,
+                    protocol_stats,
+                    game_server: self.server.pubkey(),
+                    vault_state,
+                    vault_token_account,
+                    mint: self.mint,
+                    treasury_token_account,
+                    token_program: spl_token::ID,
+                    system_program: solana_sdk::system_program::ID,
+                }
+                .to_account_metas(None),
+                &self.server,
+            ),
+            Op::Refund => {
+                let mut metas = accounts::RefundWager {
+                    game_session: session,
+                    game_config: config,
+                    refund_state: self.pda(b"refund"),
+                    vault_state,
+                    vault_token_account,
+                    mint: self.mint,
+                    mint_stats,
+                    protocol_stats,
+                    game_server: self.server.pubkey(),
+                    instructions: INSTRUCTIONS_SYSVAR_ID,
+                    token_program: spl_token::ID,
+                    system_program: solana_sdk::system_program::ID,
+                }
+                .to_account_metas(None);
+                // every player's ATA, in roster order (FH-005 derives and checks them)
+                metas.extend(self.players.iter().map(|p| solana_sdk::instruction::AccountMeta::new(ata(p), false)));
+                (instruction::RefundWager { session_id: SESSION_ID }.data(), metas, &self.server)
+            }
+            Op::ClaimRefund { player } => {
+                let user = &self.players[player as usize];
+                (
+                    instruction::ClaimRefund { session_id: SESSION_ID }.data(),
+                    accounts::ClaimRefund {
+                        game_session: session,
+                        game_config: config,
+                        refund_state: self.pda(b"refund"),
+                        settlement: self.pda(b"settlement"),
+                        player: user.pubkey(),
+                        player_token_account: ata(user),
+                        vault_state,
+                        vault_token_account,
+                        mint: self.mint,
+                        mint_stats,
+                        protocol_stats,
+                        instructions: INSTRUCTIONS_SYSVAR_ID,
+                        token_program: spl_token::ID,
+                        system_program: solana_sdk::system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    user,
+                )
+            }
+            Op::Crank { .. } => unreachable!("handled in apply"),
+        };
+        (Instruction { program_id: PROGRAM_ID, accounts: metas, data }, signer)
+    }
+
+    /// Kills and spawn purchases name a side; even players are tried on A and
+    /// odd on B, and a wrong guess is just another rejected input
+    fn side_of(&self, player: u8) -> TeamSide {
+        if player % 2 == 0 { TeamSide::A } else { TeamSide::B }
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> Option<T> {
+        let account = self.ctx.banks_client.get_account(key).await.ok()??;
+        T::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// invariants::check_all on the live accounts; None while no session exists
+    async fn check(&mut self) -> Result<(), String> {
+        let Some(session) = self.load::<GameSession>(self.session()).await else { return Ok(()) };
+        let vault_state: VaultState = self.load(self.pda(b"vault")).await.ok_or("vault state missing")?;
+        let config: GameConfig = self.load(setup::global(b"config")).await.ok_or("config missing")?;
+        let settlement: Option<Settlement> = self.load(self.pda(b"settlement")).await;
+        let vault_amount = setup::token_balance(&mut self.ctx, self.pda(b"vault_token")).await;
+
+        let snapshot = invariants::Snapshot {
+            session: &session,
+            prev_status: self.prev_status.clone(),
+            vault_state: &vault_state,
+            vault_amount,
+            settlement: settlement.as_ref(),
+            config: &config,
+        };
+        invariants::check_all(&snapshot).map_err(|e| e.to_string())?;
+        self.prev_status = Some(session.status.clone());
+        Ok(())
+    }
+}
+
+fn game_mode(mode: u8) -> GameMode {
+    match mode {
+        0 => GameMode::WinnerTakesAllOneVsOne,
+        1 => GameMode::WinnerTakesAllThreeVsThree,
+        2 => GameMode::WinnerTakesAllFiveVsFive,
+        3 => GameMode::PayToSpawnOneVsOne,
+        4 => GameMode::PayToSpawnThreeVsThree,
+        _ => GameMode::PayToSpawnFiveVsFive,
+    }
+}
+
+/// Runs one sequence on a fresh bank; Err names the op that broke an
+/// invariant. A run where no create or no join got through never touched the
+/// program's money paths, so it fails too rather than passing vacuously.
+fn run(ops: &[Op]) -> Result<(), String> {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let mut harness = Harness::new().await;
+        let (mut creates, mut joins) = (0, 0);
+        for (i, op) in ops.iter().enumerate() {
+            if harness.apply(op).await? {
+                match op {
+                    Op::Create { .. } => creates += 1,
+                    Op::Join { .. } => joins += 1,
+                    _ => {}
+                }
+                harness.check().await.map_err(|e| format!("op {i} {op:?}: {e}"))?;
+            }
+        }
+        if creates == 0 || joins == 0 {
+            return Err(format!("setup never reached the program: {creates} creates, {joins} joins accepted"));
+        }
+        Ok(())
+    })
+}
+
+fn parse_op(text: &str) -> Op {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    let num = |i: usize| parts[i].parse::<i64>().unwrap_or_else(|_| panic!("bad number in `{text}`"));
+    let side = |i: usize| if parts[i] == "A" { TeamSide::A } else { TeamSide::B };
+    match parts[0] {
+        "create" => Op::Create { mode: num(1) as u8, bet: num(2) as u64 },
+        "join" => Op::Join { player: num(1) as u8, side: side(2) },
+        "kill" => Op::Kill { killer: num(1) as u8, victim: num(2) as u8 },
+        "buy" => Op::BuySpawns { player: num(1) as u8 },
+        "leave" => Op::Leave { player: num(1) as u8 },
+        "start" => Op::Start,
+        "distribute" => Op::Distribute { winner: side(1) },
+        "refund" => Op::Refund,
+        "claim_refund" => Op::ClaimRefund { player: num(1) as u8 },
+        "crank" => Op::Crank { advance_secs: num(1) },
+        other => panic!("unknown op `{other}`"),
+    }
+}
+
+fn runner(seed: u64) -> TestRunner {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    TestRunner::new_with_rng(Config { cases: 1, ..Config::default() }, TestRng::from_seed(RngAlgorithm::ChaCha, &bytes))
+}
+
+fn run_seed(seed: u64) {
+    let ops = sequence()
+        .new_tree(&mut runner(seed))
+        .unwrap()
+        .current();
+    run(&ops).unwrap_or_else(|e| panic!("seed {seed}: {e}"));
+}
+
+/// `seed <u64>` replays a random run; `ops a | b | ...` is a hand-written sequence
+#[test]
+fn regressions() {
+    for line in REGRESSIONS.lines().map(|l| l.split('#').next().unwrap().trim()).filter(|l| !l.is_empty()) {
+        if let Some(seed) = line.strip_prefix("seed ") {
+            run_seed(seed.parse().expect("seed is a u64"));
+        } else if let Some(ops) = line.strip_prefix("ops ") {
+            let ops: Vec<Op> = ops.split('|').map(|op| parse_op(op.trim())).collect();
+            run(&ops).unwrap_or_else(|e| panic!("`{line}`: {e}"));
+        }
+    }
+}
+
+#[test]
+fn random_sequences_hold_invariants() {
+    if let Ok(seed) = std::env::var("FUZZ_SEED") {
+        return run_seed(seed.parse().expect("FUZZ_SEED is a u64"));
+    }
+    // Fixed list: the same 256 sequences on every CI run. Widen the search by
+    // changing the range locally, not by reseeding in CI.
+    for seed in 0..RANDOM_RUNS {
+        let result = std::panic::catch_unwind(|| run_seed(seed));
+        assert!(result.is_ok(), "failing run: add `seed {seed}` to tests/fuzz-regressions.txt");
+    }
+}
+
+mod setup {
+    use super::*;
+    use solana_sdk::{program_pack::Pack, system_instruction};
+
+    const PLAYER_BALANCE: u64 = u64::MAX / 1024;
+
+    /// Program-wide PDAs: config, registry, protocol_stats, treasury
+    pub fn global(seed: &[u8]) -> Pubkey {
+        Pubkey::find_program_address(&[seed], &PROGRAM_ID).0
+    }
+
+    pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        spl_associated_token_account::get_associated_token_address(owner, mint)
+    }
+
+    pub async fn token_balance(ctx: &mut ProgramTestContext, account: Pubkey) -> u64 {
+        match ctx.banks_client.get_account(account).await.unwrap() {
+            Some(a) => spl_token::state::Account::unpack(&a.data).unwrap().amount,
+            None => 0,
+        }
+    }
+
+    async fn send(ctx: &mut ProgramTestContext, ixs: &[Instruction], signers: &[&Keypair]) {
+        let blockhash = ctx.get_new_latest_blockhash().await.unwrap();
+        let mut all: Vec<&Keypair> = vec![&ctx.payer];
+        all.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&ctx.payer.pubkey()), &all, blockhash);
+        ctx.banks_client.process_transaction(tx).await.unwrap();
+    }
+
+    /// SOL for everyone, one mint, a funded ATA per player, the config (with
+    /// registry and protocol stats), that mint allowlisted with its MintStats,
+    /// the treasury ATA, and the server authorized with every role. Returns
+    /// the mint.
+    pub async fn bootstrap(ctx: &mut ProgramTestContext, server: &Keypair, players: &[Keypair]) -> Pubkey {
+        let payer = ctx.payer.pubkey();
+        let fund: Vec<Instruction> = std::iter::once(server)
+            .chain(players)
+            .map(|k| system_instruction::transfer(&payer, &k.pubkey(), 10_000_000_000))
+            .collect();
+        send(ctx, &fund, &[]).await;
+
+        let mint = Keypair::new();
+        let rent = ctx.banks_client.get_rent().await.unwrap().minimum_balance(spl_token::state::Mint::LEN);
+        send(
+            ctx,
+            &[
+                system_instruction::create_account(&payer, &mint.pubkey(), rent, spl_token::state::Mint::LEN as u64, &spl_token::ID),
+                spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &payer, None, 6).unwrap(),
+            ],
+            &[&mint],
+        )
+        .await;
+
+        for player in players {
+            send(
+                ctx,
+                &[
+                    spl_associated_token_account::instruction::create_associated_token_account(
+                        &payer,
+                        &player.pubkey(),
+                        &mint.pubkey(),
+                        &spl_token::ID,
+                    ),
+                    spl_token::instruction::mint_to(
+                        &spl_token::ID,
+                        &mint.pubkey(),
+                        &ata(&player.pubkey(), &mint.pubkey()),
+                        &payer,
+                        &[],
+                        PLAYER_BALANCE,
+                    )
+                    .unwrap(),
+                ],
+                &[],
+            )
+            .await;
+        }
+
+        // fee destination for finalize / sweep (FC-005)
+        send(
+            ctx,
+            &[spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                &global(b"treasury"),
+                &mint.pubkey(),
+                &spl_token::ID,
+            )],
+            &[],
+        )
+        .await;
+
+        let config = global(b"config");
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::InitializeConfig {
+                game_config: config,
+                registry: global(b"registry"),
+                protocol_stats: global(b"protocol_stats"),
+                admin: payer,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeConfig {
+                min_bet_amount: 1,
+                max_bet_amount: PLAYER_BALANCE / 64,
+                spawn_cost_divisor: 10,
+                initial_spawn_count: 10,
+                max_spawns_per_player: 50,
+                protocol_fee_bps: 250,
+                max_game_duration: 3_600,
+                update_delay_seconds: 86_400,
+            }
+            .data(),
+        };
+        let allow_mint = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::SetAllowedMint {
+                game_config: config,
+                mint_stats: Pubkey::find_program_address(&[b"mint_stats", mint.pubkey().as_ref()], &PROGRAM_ID).0,
+                admin: payer,
+                mint: mint.pubkey(),
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::SetAllowedMint { burn_share_bps: 0 }.data(),
+        };
+        let allow_server = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::AddAuthorizedServer {
+                game_config: config,
+                authorized_server: Pubkey::find_program_address(&[b"server", server.pubkey().as_ref()], &PROGRAM_ID).0,
+                admin: payer,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            // without roles every create fails MissingServerRole (server_roles)
+            data: instruction::AddAuthorizedServer { server: server.pubkey(), roles: server_roles::ALL }.data(),
+        };
+        send(ctx, &[ix, allow_mint, allow_server], &[]).await;
+        mint.pubkey()
+    }
+}